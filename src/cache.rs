@@ -0,0 +1,433 @@
+use byte_unit::Byte;
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use crate::utils::CommandOutput;
+
+/* The version cache keeps the output of commands such as `python --version` on disk, so
+that they don't need to be run on every prompt. Each output is stored in its own file,
+named after a hash of everything the output depends on: the resolved path of the binary,
+its modification time and size, the arguments, and the values of any environment
+variables given by the module. Upgrading a binary therefore changes its key, and the old
+entry is simply never read again (until `starship cache clear` removes it).
+
+Version manager shims (e.g. pyenv, rbenv or Volta) pick a version based on the current
+directory, which can't be part of the key, so their output is never cached.
+
+The entries are kept in their own subdirectory of the cache directory, so that clearing the
+cache never removes other files, even if `$STARSHIP_CACHE` is a shared directory.
+*/
+
+/// The subdirectory of the cache directory holding the cached outputs
+const ENTRIES_DIR: &str = "cmd";
+
+/// Get the directory of the cache, which is `$XDG_CACHE_HOME/starship` on Linux.
+/// It can be overridden with `$STARSHIP_CACHE`.
+pub fn cache_dir() -> Option<PathBuf> {
+    if let Some(dir) = env::var_os("STARSHIP_CACHE") {
+        return Some(PathBuf::from(dir));
+    }
+    Some(dirs::cache_dir()?.join("starship"))
+}
+
+/// Return the cached output of a command, or run it with `exec` and cache its output
+pub fn get_or_insert<F>(cmd: &str, args: &[&str], env: &[&str], exec: F) -> Option<CommandOutput>
+where
+    F: FnOnce() -> Option<CommandOutput>,
+{
+    match cache_dir() {
+        Some(dir) => get_or_insert_in(&dir.join(ENTRIES_DIR), cmd, args, env, exec),
+        None => exec(),
+    }
+}
+
+fn get_or_insert_in<F>(
+    dir: &Path,
+    cmd: &str,
+    args: &[&str],
+    env: &[&str],
+    exec: F,
+) -> Option<CommandOutput>
+where
+    F: FnOnce() -> Option<CommandOutput>,
+{
+    let key = match cache_key(cmd, args, env) {
+        Some(key) => key,
+        None => return exec(),
+    };
+    let path = dir.join(&key);
+
+    if let Some(output) = read_entry(&path) {
+        log::trace!("Using the cached output of {} {:?}", cmd, args);
+        return Some(output);
+    }
+
+    let output = exec()?;
+    if let Err(error) = write_entry(dir, &key, cmd, args, &output) {
+        log::debug!(
+            "Unable to cache the output of {} {:?}: {}",
+            cmd,
+            args,
+            error
+        );
+    }
+    Some(output)
+}
+
+/// Compute the key of a command, or `None` if its output shouldn't be cached
+fn cache_key(cmd: &str, args: &[&str], env: &[&str]) -> Option<String> {
+    let binary = find_binary(cmd)?;
+    // Resolve symlinks (e.g. `python` -> `python3.8`), so that the modification time is
+    // the one of the actual binary
+    let resolved = fs::canonicalize(&binary).ok()?;
+    if is_shim(&binary) || is_shim(&resolved) {
+        log::trace!("Not caching the output of {:?}, as it is a shim", binary);
+        return None;
+    }
+
+    let binary = resolved;
+    let metadata = fs::metadata(&binary).ok()?;
+    let modified = metadata
+        .modified()
+        .ok()?
+        .duration_since(UNIX_EPOCH)
+        .ok()?
+        .as_nanos();
+
+    // Each field is followed by a `\0`, and the arguments and variables by their count, so
+    // that e.g. the arguments `["ab"]` and `["a", "b"]` have different keys
+    let mut hasher = Fnv1a::default();
+    hasher.write(binary.to_string_lossy().as_bytes());
+    hasher.write(modified.to_string().as_bytes());
+    hasher.write(metadata.len().to_string().as_bytes());
+    hasher.write(args.len().to_string().as_bytes());
+    for arg in args {
+        hasher.write(arg.as_bytes());
+    }
+    hasher.write(env.len().to_string().as_bytes());
+    for name in env {
+        hasher.write(name.as_bytes());
+        hasher.write(env::var(name).unwrap_or_default().as_bytes());
+    }
+
+    Some(format!("{:016x}", hasher.finish()))
+}
+
+/// Whether a binary is a version manager shim: one in a `shims` directory (e.g. pyenv,
+/// rbenv or asdf), or one of Volta, which are links to `volta-shim` in `~/.volta/bin`
+fn is_shim(binary: &Path) -> bool {
+    let is_volta_shim = matches!(
+        binary.file_stem().and_then(|stem| stem.to_str()),
+        Some("volta-shim")
+    );
+    is_volta_shim
+        || binary
+            .components()
+            .any(|component| component.as_os_str() == "shims" || component.as_os_str() == ".volta")
+}
+
+/// Find the binary which would be executed for `cmd`, searching `$PATH` as the OS does
+fn find_binary(cmd: &str) -> Option<PathBuf> {
+    let cmd = Path::new(cmd);
+    if cmd.components().count() > 1 {
+        return Some(cmd.to_path_buf()).filter(|path| path.is_file());
+    }
+
+    let extensions: &[&str] = if cfg!(windows) {
+        &["exe", "cmd", "bat"]
+    } else {
+        &[]
+    };
+
+    env::split_paths(&env::var_os("PATH")?).find_map(|dir| {
+        let path = dir.join(cmd);
+        if path.is_file() {
+            return Some(path);
+        }
+        extensions
+            .iter()
+            .map(|extension| path.with_extension(extension))
+            .find(|path| path.is_file())
+    })
+}
+
+fn read_entry(path: &Path) -> Option<CommandOutput> {
+    let data = fs::read_to_string(path).ok()?;
+    let entry: serde_json::Value = serde_json::from_str(&data).ok()?;
+
+    Some(CommandOutput {
+        stdout: entry.get("stdout")?.as_str()?.to_string(),
+        stderr: entry.get("stderr")?.as_str()?.to_string(),
+    })
+}
+
+fn write_entry(
+    dir: &Path,
+    key: &str,
+    cmd: &str,
+    args: &[&str],
+    output: &CommandOutput,
+) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+
+    let entry = json!({
+        "command": cmd,
+        "args": args,
+        "stdout": output.stdout,
+        "stderr": output.stderr,
+    });
+
+    // Modules are computed in parallel, so the entry is written to a temporary file first
+    // to never leave a partially written entry behind
+    let temp_path = dir.join(format!("{}.{}.tmp", key, std::process::id()));
+    fs::write(&temp_path, entry.to_string())?;
+    fs::rename(temp_path, dir.join(key))
+}
+
+/// Remove every entry of the cache, returning how many were removed
+fn clear_in(dir: &Path) -> io::Result<usize> {
+    let mut count = 0;
+    let entries_dir = dir.join(ENTRIES_DIR);
+    if entries_dir.is_dir() {
+        for entry in fs::read_dir(&entries_dir)? {
+            let path = entry?.path();
+            if path.is_file() {
+                fs::remove_file(path)?;
+                count += 1;
+            }
+        }
+        fs::remove_dir(entries_dir)?;
+    }
+
+    // The markers of the warnings which were already shown in each shell session
+    let sessions = dir.join("sessions");
+    if sessions.is_dir() {
+        fs::remove_dir_all(sessions)?;
+    }
+    Ok(count)
+}
+
+/// The number of entries and total size of the cache, with the number of entries per command
+#[derive(Debug, Default, PartialEq)]
+struct CacheStats {
+    entries: usize,
+    size: u64,
+    commands: BTreeMap<String, usize>,
+}
+
+fn stats_in(dir: &Path) -> io::Result<CacheStats> {
+    let mut stats = CacheStats::default();
+    for entry in fs::read_dir(dir.join(ENTRIES_DIR))? {
+        let path = entry?.path();
+        if !path.is_file() {
+            continue;
+        }
+
+        stats.entries += 1;
+        stats.size += fs::metadata(&path)?.len();
+
+        let command = fs::read_to_string(&path)
+            .ok()
+            .and_then(|data| serde_json::from_str::<serde_json::Value>(&data).ok())
+            .and_then(|entry| Some(entry.get("command")?.as_str()?.to_string()))
+            .unwrap_or_else(|| "(unreadable)".to_string());
+        *stats.commands.entry(command).or_insert(0) += 1;
+    }
+    Ok(stats)
+}
+
+pub fn clear() {
+    let dir = match cache_dir() {
+        Some(dir) => dir,
+        None => {
+            eprintln!("Unable to find the cache directory");
+            return;
+        }
+    };
+
+    match clear_in(&dir) {
+        Ok(count) => println!("Removed {} entries from {}", count, dir.display()),
+        Err(ref error) if error.kind() == io::ErrorKind::NotFound => {
+            println!("The cache at {} is already empty", dir.display())
+        }
+        Err(error) => eprintln!("Unable to clear the cache at {}: {}", dir.display(), error),
+    }
+}
+
+pub fn stats() {
+    let dir = match cache_dir() {
+        Some(dir) => dir,
+        None => {
+            eprintln!("Unable to find the cache directory");
+            return;
+        }
+    };
+
+    let stats = match stats_in(&dir) {
+        Ok(stats) => stats,
+        Err(ref error) if error.kind() == io::ErrorKind::NotFound => CacheStats::default(),
+        Err(error) => {
+            eprintln!("Unable to read the cache at {}: {}", dir.display(), error);
+            return;
+        }
+    };
+
+    println!("Cache directory: {}", dir.display());
+    println!("Entries: {}", stats.entries);
+    println!(
+        "Size: {}",
+        Byte::from_bytes(u128::from(stats.size)).get_appropriate_unit(false)
+    );
+    for (command, count) in stats.commands {
+        println!("  {}: {}", command, count);
+    }
+}
+
+/// Hash `data` into a short string which is stable across runs, to be used in file names
+pub fn hash(data: &str) -> String {
+    let mut hasher = Fnv1a::default();
+    hasher.write(data.as_bytes());
+    format!("{:016x}", hasher.finish())
+}
+
+/// The 64-bit FNV-1a hash, which is stable across versions of Rust (unlike `DefaultHasher`)
+struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Fnv1a(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Fnv1a {
+    /// Hash `bytes`, followed by a separator so that ("ab", "c") and ("a", "bc") differ
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes.iter().chain(&[0]) {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+#[cfg(test)]
+#[cfg(not(windows))] // These tests assume a Unix-like environment.
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    fn output(stdout: &str) -> Option<CommandOutput> {
+        Some(CommandOutput {
+            stdout: stdout.to_string(),
+            stderr: String::new(),
+        })
+    }
+
+    #[test]
+    fn cached_output_is_reused() -> io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let runs = Cell::new(0);
+        let exec = || {
+            runs.set(runs.get() + 1);
+            output("v1.0.0")
+        };
+
+        let first = get_or_insert_in(dir.path(), "/bin/sh", &["--version"], &[], exec);
+        let second = get_or_insert_in(dir.path(), "/bin/sh", &["--version"], &[], exec);
+
+        assert_eq!(first, output("v1.0.0"));
+        assert_eq!(second, output("v1.0.0"));
+        assert_eq!(runs.get(), 1);
+        dir.close()
+    }
+
+    #[test]
+    fn failed_output_is_not_cached() -> io::Result<()> {
+        let dir = tempfile::tempdir()?;
+
+        let entries_dir = dir.path().join(ENTRIES_DIR);
+
+        assert_eq!(
+            get_or_insert_in(&entries_dir, "/bin/sh", &[], &[], || None),
+            None
+        );
+        assert_eq!(stats_in(dir.path()).ok(), None);
+        dir.close()
+    }
+
+    #[test]
+    fn key_depends_on_binary_args_and_env() {
+        let key = cache_key("/bin/sh", &["--version"], &[]).unwrap();
+
+        assert_eq!(cache_key("/bin/sh", &["--version"], &[]), Some(key.clone()));
+        assert_ne!(cache_key("/bin/sh", &["-v"], &[]), Some(key.clone()));
+        assert_ne!(
+            cache_key("/bin/cat", &["--version"], &[]),
+            Some(key.clone())
+        );
+        assert_ne!(cache_key("/bin/sh", &["--version"], &["PATH"]), Some(key));
+        assert_ne!(
+            cache_key("/bin/sh", &["ab"], &[]),
+            cache_key("/bin/sh", &["a", "b"], &[])
+        );
+        assert_ne!(
+            cache_key("/bin/sh", &["PATH"], &[]),
+            cache_key("/bin/sh", &[], &["PATH"])
+        );
+    }
+
+    #[test]
+    fn shims_and_missing_binaries_are_not_cached() -> io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let shims = dir.path().join("shims");
+        fs::create_dir(&shims)?;
+        fs::write(shims.join("python"), "")?;
+
+        assert_eq!(
+            cache_key(&shims.join("python").to_string_lossy(), &[], &[]),
+            None
+        );
+
+        // Volta's binaries are links to its shim
+        let volta_bin = dir.path().join(".volta").join("bin");
+        fs::create_dir_all(&volta_bin)?;
+        fs::write(volta_bin.join("volta-shim"), "")?;
+        std::os::unix::fs::symlink(volta_bin.join("volta-shim"), volta_bin.join("node"))?;
+        assert_eq!(
+            cache_key(&volta_bin.join("node").to_string_lossy(), &[], &[]),
+            None
+        );
+        assert!(is_shim(Path::new("/opt/bin/volta-shim")));
+        assert!(!is_shim(Path::new("/usr/bin/node")));
+        assert_eq!(cache_key("/this/binary/does/not/exist", &[], &[]), None);
+        dir.close()
+    }
+
+    #[test]
+    fn stats_and_clear() -> io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let entries_dir = dir.path().join(ENTRIES_DIR);
+        get_or_insert_in(&entries_dir, "/bin/sh", &["-a"], &[], || output("a"));
+        get_or_insert_in(&entries_dir, "/bin/sh", &["-b"], &[], || output("b"));
+        // Other files in the cache directory aren't entries
+        fs::write(dir.path().join("unrelated"), "")?;
+
+        let stats = stats_in(dir.path())?;
+        assert_eq!(stats.entries, 2);
+        assert!(stats.size > 0);
+        assert_eq!(stats.commands.get("/bin/sh"), Some(&2));
+
+        assert_eq!(clear_in(dir.path())?, 2);
+        assert!(stats_in(dir.path()).is_err());
+        assert!(dir.path().join("unrelated").is_file());
+        dir.close()
+    }
+}
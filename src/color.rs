@@ -0,0 +1,313 @@
+use std::env;
+
+use ansi_term::{Color, Style};
+use once_cell::sync::OnceCell;
+use serde_json::json;
+
+use crate::config::ModuleConfig;
+
+/// The colors which the terminal can show, chosen with the `color_mode` option
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ColorMode {
+    /// Detect the colors from the environment
+    Auto,
+    TrueColor,
+    Ansi256,
+    Ansi16,
+    /// No styling at all
+    None,
+}
+
+impl<'a> ModuleConfig<'a> for ColorMode {
+    fn from_config(config: &toml::Value) -> Option<Self> {
+        match config.as_str()? {
+            "auto" => Some(ColorMode::Auto),
+            "truecolor" => Some(ColorMode::TrueColor),
+            "256" => Some(ColorMode::Ansi256),
+            "16" => Some(ColorMode::Ansi16),
+            "none" => Some(ColorMode::None),
+            _ => None,
+        }
+    }
+
+    fn schema() -> serde_json::Value {
+        json!({ "type": "string", "enum": ["auto", "truecolor", "256", "16", "none"] })
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        match self {
+            ColorMode::Auto => json!("auto"),
+            ColorMode::TrueColor => json!("truecolor"),
+            ColorMode::Ansi256 => json!("256"),
+            ColorMode::Ansi16 => json!("16"),
+            ColorMode::None => json!("none"),
+        }
+    }
+}
+
+/// The color mode used to render the prompt. Segments are painted without access to the
+/// config, so it is set once the config has been loaded.
+static COLOR_MODE: OnceCell<ColorMode> = OnceCell::new();
+
+/// Use `mode` to render the prompt, detecting the colors of the terminal for `Auto`
+pub fn set_color_mode(mode: ColorMode) {
+    let mode = match mode {
+        ColorMode::Auto => detect_color_mode(),
+        mode => mode,
+    };
+    log::debug!("Using the color mode {:?}", mode);
+    if COLOR_MODE.set(mode).is_err() {
+        log::debug!("The color mode was already set");
+    }
+}
+
+/// Detect the colors of the terminal from `$NO_COLOR`, `$COLORTERM` and `$TERM`
+fn detect_color_mode() -> ColorMode {
+    let no_color = matches!(env::var_os("NO_COLOR"), Some(value) if !value.is_empty());
+    let colorterm = env::var("COLORTERM").ok();
+    let term = env::var("TERM").ok();
+    color_mode_from_env(no_color, colorterm.as_deref(), term.as_deref())
+}
+
+fn color_mode_from_env(no_color: bool, colorterm: Option<&str>, term: Option<&str>) -> ColorMode {
+    if no_color {
+        return ColorMode::None;
+    }
+    if let Some("truecolor") | Some("24bit") = colorterm {
+        return ColorMode::TrueColor;
+    }
+
+    match term {
+        // Without $TERM (e.g. on Windows), there is nothing to go by, so colors are kept
+        None | Some("") => ColorMode::TrueColor,
+        Some("dumb") => ColorMode::None,
+        Some(term) if term.ends_with("-direct") || term.contains("truecolor") => {
+            ColorMode::TrueColor
+        }
+        Some(term) if term.contains("256color") => ColorMode::Ansi256,
+        Some(_) => ColorMode::Ansi16,
+    }
+}
+
+/// Adapt a style to the colors of the terminal, as set with `set_color_mode`
+pub fn adapt(style: Style) -> Style {
+    adapt_style(
+        style,
+        COLOR_MODE.get().copied().unwrap_or(ColorMode::TrueColor),
+    )
+}
+
+/// Replace the colors of a style with the nearest colors available in `mode`
+fn adapt_style(style: Style, mode: ColorMode) -> Style {
+    match mode {
+        ColorMode::Auto | ColorMode::TrueColor => style,
+        ColorMode::None => Style::new(),
+        ColorMode::Ansi256 => Style {
+            foreground: style.foreground.map(to_ansi_256),
+            background: style.background.map(to_ansi_256),
+            ..style
+        },
+        ColorMode::Ansi16 => {
+            let mut adapted = Style {
+                foreground: None,
+                background: style.background.map(|color| to_ansi_16(color).0),
+                ..style
+            };
+            if let Some((color, is_bright)) = style.foreground.map(to_ansi_16) {
+                // The 16 color escape codes have no bright colors, which are shown as bold
+                // text by most terminals with 16 colors
+                adapted.foreground = Some(color);
+                adapted.is_bold |= is_bright;
+            }
+            adapted
+        }
+    }
+}
+
+/// The levels of each component of the colors of the 6x6x6 cube of the 256 colors
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// The colors of the 16 standard colors, as shown by xterm
+const ANSI_16_COLORS: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    (205, 0, 0),
+    (0, 205, 0),
+    (205, 205, 0),
+    (0, 0, 238),
+    (205, 0, 205),
+    (0, 205, 205),
+    (229, 229, 229),
+    (127, 127, 127),
+    (255, 0, 0),
+    (0, 255, 0),
+    (255, 255, 0),
+    (92, 92, 255),
+    (255, 0, 255),
+    (0, 255, 255),
+    (255, 255, 255),
+];
+
+const BASE_COLORS: [Color; 8] = [
+    Color::Black,
+    Color::Red,
+    Color::Green,
+    Color::Yellow,
+    Color::Blue,
+    Color::Purple,
+    Color::Cyan,
+    Color::White,
+];
+
+/// Convert an RGB color to the nearest color of the cube or grayscale ramp of the 256
+/// colors. The first 16 colors are left out, as they depend on the terminal's theme.
+fn to_ansi_256(color: Color) -> Color {
+    let (r, g, b) = match color {
+        Color::RGB(r, g, b) => (r, g, b),
+        color => return color,
+    };
+
+    let nearest_level = |value: u8| {
+        (0..CUBE_LEVELS.len())
+            .min_by_key(|i| (i32::from(CUBE_LEVELS[*i]) - i32::from(value)).abs())
+            .unwrap_or(0)
+    };
+    let (ri, gi, bi) = (nearest_level(r), nearest_level(g), nearest_level(b));
+    let cube_index = 16 + 36 * ri + 6 * gi + bi;
+    let cube_color = (CUBE_LEVELS[ri], CUBE_LEVELS[gi], CUBE_LEVELS[bi]);
+
+    let average = (u32::from(r) + u32::from(g) + u32::from(b)) / 3;
+    let gray_step = (average.saturating_sub(3) / 10).min(23) as usize;
+    let gray_level = (8 + 10 * gray_step) as u8;
+    let gray_index = 232 + gray_step;
+
+    let index = if distance((r, g, b), (gray_level, gray_level, gray_level))
+        < distance((r, g, b), cube_color)
+    {
+        gray_index
+    } else {
+        cube_index
+    };
+    Color::Fixed(index as u8)
+}
+
+/// Convert a color to the nearest of the 8 base colors, and whether it is the bright
+/// version of that color
+fn to_ansi_16(color: Color) -> (Color, bool) {
+    let rgb = match color {
+        Color::Fixed(index) if index < 16 => {
+            return (BASE_COLORS[usize::from(index % 8)], index >= 8);
+        }
+        Color::Fixed(index) => fixed_to_rgb(index),
+        Color::RGB(r, g, b) => (r, g, b),
+        color => return (color, false),
+    };
+
+    let index = (0..ANSI_16_COLORS.len())
+        .min_by_key(|i| distance(rgb, ANSI_16_COLORS[*i]))
+        .unwrap_or(0);
+    (BASE_COLORS[index % 8], index >= 8)
+}
+
+/// The RGB value of one of the 256 colors
+fn fixed_to_rgb(index: u8) -> (u8, u8, u8) {
+    match index {
+        0..=15 => ANSI_16_COLORS[usize::from(index)],
+        16..=231 => {
+            let index = usize::from(index - 16);
+            (
+                CUBE_LEVELS[index / 36],
+                CUBE_LEVELS[index / 6 % 6],
+                CUBE_LEVELS[index % 6],
+            )
+        }
+        _ => {
+            let level = 8 + 10 * (index - 232);
+            (level, level, level)
+        }
+    }
+}
+
+fn distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+    let component = |a: u8, b: u8| (i32::from(a) - i32::from(b)).pow(2) as u32;
+    component(a.0, b.0) + component(a.1, b.1) + component(a.2, b.2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_color_mode() {
+        assert_eq!(
+            color_mode_from_env(true, Some("truecolor"), None),
+            ColorMode::None
+        );
+        assert_eq!(
+            color_mode_from_env(false, Some("24bit"), Some("linux")),
+            ColorMode::TrueColor
+        );
+        assert_eq!(
+            color_mode_from_env(false, None, Some("xterm-256color")),
+            ColorMode::Ansi256
+        );
+        assert_eq!(
+            color_mode_from_env(false, None, Some("xterm-direct")),
+            ColorMode::TrueColor
+        );
+        assert_eq!(
+            color_mode_from_env(false, None, Some("linux")),
+            ColorMode::Ansi16
+        );
+        assert_eq!(
+            color_mode_from_env(false, None, Some("screen")),
+            ColorMode::Ansi16
+        );
+        assert_eq!(
+            color_mode_from_env(false, None, Some("dumb")),
+            ColorMode::None
+        );
+        assert_eq!(color_mode_from_env(false, None, None), ColorMode::TrueColor);
+    }
+
+    #[test]
+    fn downsamples_to_256_colors() {
+        assert_eq!(to_ansi_256(Color::RGB(255, 0, 0)), Color::Fixed(196));
+        assert_eq!(to_ansi_256(Color::RGB(0x8b, 0xe9, 0xfd)), Color::Fixed(117));
+        assert_eq!(to_ansi_256(Color::RGB(128, 128, 128)), Color::Fixed(244));
+        assert_eq!(to_ansi_256(Color::RGB(0, 0, 0)), Color::Fixed(16));
+        assert_eq!(to_ansi_256(Color::Fixed(105)), Color::Fixed(105));
+        assert_eq!(to_ansi_256(Color::Cyan), Color::Cyan);
+    }
+
+    #[test]
+    fn downsamples_to_16_colors() {
+        assert_eq!(to_ansi_16(Color::RGB(250, 10, 10)), (Color::Red, true));
+        assert_eq!(to_ansi_16(Color::RGB(190, 10, 10)), (Color::Red, false));
+        assert_eq!(to_ansi_16(Color::Fixed(105)), (Color::Blue, true));
+        assert_eq!(to_ansi_16(Color::Fixed(3)), (Color::Yellow, false));
+        assert_eq!(to_ansi_16(Color::Fixed(8)), (Color::Black, true));
+        assert_eq!(to_ansi_16(Color::Green), (Color::Green, false));
+    }
+
+    #[test]
+    fn adapts_styles() {
+        let style = Style::new()
+            .italic()
+            .fg(Color::RGB(255, 0, 0))
+            .on(Color::RGB(0, 0, 238));
+
+        assert_eq!(adapt_style(style, ColorMode::TrueColor), style);
+        assert_eq!(
+            adapt_style(style, ColorMode::Ansi256),
+            Style::new()
+                .italic()
+                .fg(Color::Fixed(196))
+                .on(Color::Fixed(21))
+        );
+        assert_eq!(
+            adapt_style(style, ColorMode::Ansi16),
+            Style::new().italic().bold().fg(Color::Red).on(Color::Blue)
+        );
+        assert_eq!(adapt_style(style, ColorMode::None), Style::new());
+    }
+}
@@ -1,13 +1,23 @@
+use crate::config_check;
 use crate::configs::StarshipRootConfig;
 use crate::utils;
 use ansi_term::{Color, Style};
 
 use std::clone::Clone;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::ffi::OsStr;
+use std::fmt;
+use std::fs;
+use std::io;
 use std::marker::Sized;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
 
 use dirs::home_dir;
+use once_cell::sync::Lazy;
+use serde_json::{json, Value as JsonValue};
 use std::env;
+use toml::value::Table;
 use toml::Value;
 
 /// Root config of a module.
@@ -49,6 +59,201 @@ where
     fn load_config(&self, config: &'a Value) -> Self {
         Self::from_config(config).unwrap_or_else(|| self.clone())
     }
+
+    /// The names of the options of the config, if it is a table.
+    const KNOWN_FIELDS: &'static [&'static str] = &[];
+
+    /// Check a toml value for problems which `load_config` would silently ignore. Style
+    /// strings can use the colors of `palette`.
+    fn validate(config: &'a Value, _palette: &Palette) -> Vec<ConfigDiagnostic> {
+        match Self::from_config(config) {
+            Some(_) => Vec::new(),
+            None => vec![ConfigDiagnostic::new(DiagnosticKind::InvalidValue)],
+        }
+    }
+
+    /// Describe the accepted toml values as a JSON schema, without defaults.
+    fn schema() -> JsonValue {
+        json!({})
+    }
+
+    /// Convert `self` to JSON, to be used as the default value in a JSON schema.
+    fn to_json(&self) -> JsonValue {
+        JsonValue::Null
+    }
+}
+
+/// A problem found while checking the configuration
+#[derive(Debug, PartialEq)]
+pub struct ConfigDiagnostic {
+    /// The path of the offending value, e.g. `["git_status", "ahead", "style"]`
+    pub path: Vec<String>,
+    pub kind: DiagnosticKind,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum DiagnosticKind {
+    /// The file isn't valid TOML. The message includes the line and column.
+    Syntax(String),
+    /// The file can't be read, e.g. an included file which doesn't exist
+    UnreadableFile(String),
+    /// A table which doesn't configure any module, with the closest module name
+    UnknownModule { suggestion: Option<String> },
+    /// A key which isn't used by the config, with the closest known key
+    UnknownKey { suggestion: Option<String> },
+    /// A value which can't be read, so the default is used instead
+    InvalidValue,
+    /// A value of the wrong type, with the names of the expected and actual TOML types
+    TypeMismatch {
+        expected: &'static str,
+        actual: &'static str,
+    },
+    /// An integer which is outside of the range accepted by the option
+    OutOfRange { value: i64, min: i64 },
+    /// A style string which can't be parsed
+    InvalidStyle(String),
+    /// A format string which can't be parsed
+    InvalidFormat(String),
+    /// A `palette` which isn't defined in `palettes`, with the closest palette name
+    UnknownPalette { suggestion: Option<String> },
+    /// A color of a palette which can't be parsed
+    InvalidColor(String),
+    /// An option which still works, but has been replaced by the named option
+    Deprecated { replacement: &'static str },
+}
+
+impl ConfigDiagnostic {
+    pub fn new(kind: DiagnosticKind) -> Self {
+        ConfigDiagnostic {
+            path: Vec::new(),
+            kind,
+        }
+    }
+
+    /// Create a diagnostic for a value which isn't of the `expected` TOML type
+    pub fn type_mismatch(expected: &'static str, actual: &Value) -> Self {
+        ConfigDiagnostic::new(DiagnosticKind::TypeMismatch {
+            expected,
+            actual: actual.type_str(),
+        })
+    }
+
+    /// Create a diagnostic for an unknown key, suggesting the closest of `known_keys`
+    pub fn unknown_key(key: &str, known_keys: &[&str]) -> Self {
+        let suggestion = closest_match(key, known_keys).map(str::to_owned);
+        ConfigDiagnostic::new(DiagnosticKind::UnknownKey { suggestion }).in_key(key)
+    }
+
+    /// Prefix the path of the diagnostic with the key of its parent table
+    pub fn in_key(mut self, key: &str) -> Self {
+        self.path.insert(0, key.to_owned());
+        self
+    }
+}
+
+impl fmt::Display for ConfigDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if !self.path.is_empty() {
+            write!(f, "{}: ", self.path.join("."))?;
+        }
+
+        match &self.kind {
+            DiagnosticKind::Syntax(message) => write!(f, "{}", message),
+            DiagnosticKind::UnreadableFile(error) => {
+                write!(f, "unable to read the file: {}", error)
+            }
+            DiagnosticKind::UnknownModule { suggestion } => {
+                write!(f, "unknown module")?;
+                if let Some(suggestion) = suggestion {
+                    write!(f, ", did you mean `{}`?", suggestion)?;
+                }
+                Ok(())
+            }
+            DiagnosticKind::UnknownKey { suggestion } => {
+                write!(f, "unknown key")?;
+                if let Some(suggestion) = suggestion {
+                    write!(f, ", did you mean `{}`?", suggestion)?;
+                }
+                Ok(())
+            }
+            DiagnosticKind::InvalidValue => write!(f, "invalid value, the default is used instead"),
+            DiagnosticKind::TypeMismatch { expected, actual } => {
+                write!(
+                    f,
+                    "expected {} {}, found {}",
+                    article(expected),
+                    expected,
+                    actual
+                )
+            }
+            DiagnosticKind::OutOfRange { value, min } => {
+                write!(
+                    f,
+                    "{} is out of range, the value must be at least {}",
+                    value, min
+                )
+            }
+            DiagnosticKind::InvalidStyle(style) => write!(f, "invalid style string {:?}", style),
+            DiagnosticKind::InvalidFormat(error) => write!(f, "invalid format string: {}", error),
+            DiagnosticKind::UnknownPalette { suggestion } => {
+                write!(f, "unknown palette")?;
+                if let Some(suggestion) = suggestion {
+                    write!(f, ", did you mean `{}`?", suggestion)?;
+                }
+                Ok(())
+            }
+            DiagnosticKind::InvalidColor(color) => write!(f, "invalid color {:?}", color),
+            DiagnosticKind::Deprecated { replacement } => {
+                write!(f, "deprecated, use `{}` instead", replacement)
+            }
+        }
+    }
+}
+
+/// The indefinite article of a TOML type name
+fn article(type_name: &str) -> &'static str {
+    match type_name.chars().next() {
+        Some('a') | Some('e') | Some('i') | Some('o') | Some('u') => "an",
+        _ => "a",
+    }
+}
+
+/// Find the candidate closest to `key`, if it is close enough to be a likely typo
+pub fn closest_match<'b>(key: &str, candidates: &[&'b str]) -> Option<&'b str> {
+    let max_distance = std::cmp::max(1, key.chars().count() / 3);
+    candidates
+        .iter()
+        .map(|candidate| (edit_distance(key, candidate), *candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// The edit distance between two strings, where swapping two adjacent characters counts
+/// as a single edit (the optimal string alignment distance)
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a = a.chars().collect::<Vec<char>>();
+    let b = b.chars().collect::<Vec<char>>();
+    let mut distances = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in distances.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    distances[0] = (0..=b.len()).collect();
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            let mut distance = (distances[i - 1][j] + 1)
+                .min(distances[i][j - 1] + 1)
+                .min(distances[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(distances[i - 2][j - 2] + 1);
+            }
+            distances[i][j] = distance;
+        }
+    }
+
+    distances[a.len()][b.len()]
 }
 
 // TODO: Add logging to default implementations
@@ -56,24 +261,147 @@ impl<'a> ModuleConfig<'a> for &'a str {
     fn from_config(config: &'a Value) -> Option<Self> {
         config.as_str()
     }
+
+    fn validate(config: &'a Value, _palette: &Palette) -> Vec<ConfigDiagnostic> {
+        expect_type(config, "string")
+    }
+
+    fn schema() -> JsonValue {
+        json!({ "type": "string" })
+    }
+
+    fn to_json(&self) -> JsonValue {
+        json!(self)
+    }
+}
+
+/// Check that a value is of the given TOML type
+fn expect_type(config: &Value, expected: &'static str) -> Vec<ConfigDiagnostic> {
+    if config.type_str() == expected {
+        Vec::new()
+    } else {
+        vec![ConfigDiagnostic::type_mismatch(expected, config)]
+    }
 }
 
 impl<'a> ModuleConfig<'a> for Style {
     fn from_config(config: &Value) -> Option<Self> {
         parse_style_string(config.as_str()?)
     }
+
+    fn validate(config: &'a Value, palette: &Palette) -> Vec<ConfigDiagnostic> {
+        match config.as_str() {
+            Some(style) => validate_style(style, palette),
+            None => vec![ConfigDiagnostic::type_mismatch("string", config)],
+        }
+    }
+
+    fn schema() -> JsonValue {
+        json!({ "type": "string" })
+    }
+
+    fn to_json(&self) -> JsonValue {
+        json!(style_to_string(self))
+    }
+}
+
+impl<'a> ModuleConfig<'a> for Color {
+    fn from_config(config: &Value) -> Option<Self> {
+        parse_color_string(config.as_str()?)
+    }
+
+    fn validate(config: &'a Value, palette: &Palette) -> Vec<ConfigDiagnostic> {
+        match config.as_str() {
+            Some(color) if parse_color_or_palette_name(color, palette).is_some() => Vec::new(),
+            Some(color) => vec![ConfigDiagnostic::new(DiagnosticKind::InvalidColor(
+                color.to_owned(),
+            ))],
+            None => vec![ConfigDiagnostic::type_mismatch("string", config)],
+        }
+    }
+
+    fn schema() -> JsonValue {
+        json!({ "type": "string" })
+    }
+
+    fn to_json(&self) -> JsonValue {
+        json!(color_to_string(*self))
+    }
+}
+
+/// Check that a style string can be parsed, where colors can be named after those of
+/// `palette`. `none` is valid, although it parses to `None`.
+pub fn validate_style(style: &str, palette: &Palette) -> Vec<ConfigDiagnostic> {
+    let is_none = style
+        .split_whitespace()
+        .any(|token| token.eq_ignore_ascii_case("none"));
+    if is_none || parse_style_with_palette(style, palette).is_some() {
+        Vec::new()
+    } else {
+        vec![ConfigDiagnostic::new(DiagnosticKind::InvalidStyle(
+            style.to_owned(),
+        ))]
+    }
 }
 
 impl<'a> ModuleConfig<'a> for bool {
     fn from_config(config: &Value) -> Option<Self> {
         config.as_bool()
     }
+
+    fn validate(config: &'a Value, _palette: &Palette) -> Vec<ConfigDiagnostic> {
+        expect_type(config, "boolean")
+    }
+
+    fn schema() -> JsonValue {
+        json!({ "type": "boolean" })
+    }
+
+    fn to_json(&self) -> JsonValue {
+        json!(self)
+    }
 }
 
 impl<'a> ModuleConfig<'a> for i64 {
     fn from_config(config: &Value) -> Option<Self> {
         config.as_integer()
     }
+
+    fn validate(config: &'a Value, _palette: &Palette) -> Vec<ConfigDiagnostic> {
+        expect_type(config, "integer")
+    }
+
+    fn schema() -> JsonValue {
+        json!({ "type": "integer" })
+    }
+
+    fn to_json(&self) -> JsonValue {
+        json!(self)
+    }
+}
+
+/// The schema of a positive integer, which may also be given as a string
+fn positive_integer_schema() -> JsonValue {
+    json!({
+        "anyOf": [
+            { "type": "integer", "minimum": 1 },
+            { "type": "string", "pattern": "^[0-9]+$" },
+        ]
+    })
+}
+
+/// Check a positive integer, which may also be given as a string
+fn validate_positive_integer<T: std::str::FromStr>(config: &Value) -> Vec<ConfigDiagnostic> {
+    match config {
+        Value::Integer(value) if *value > 0 => Vec::new(),
+        Value::Integer(value) => vec![ConfigDiagnostic::new(DiagnosticKind::OutOfRange {
+            value: *value,
+            min: 1,
+        })],
+        Value::String(value) if value.parse::<T>().is_ok() => Vec::new(),
+        Value::String(_) => vec![ConfigDiagnostic::new(DiagnosticKind::InvalidValue)],
+        _ => vec![ConfigDiagnostic::type_mismatch("integer", config)],
+    }
 }
 
 impl<'a> ModuleConfig<'a> for u64 {
@@ -91,12 +419,36 @@ impl<'a> ModuleConfig<'a> for u64 {
             _ => None,
         }
     }
+
+    fn validate(config: &'a Value, _palette: &Palette) -> Vec<ConfigDiagnostic> {
+        validate_positive_integer::<u64>(config)
+    }
+
+    fn schema() -> JsonValue {
+        positive_integer_schema()
+    }
+
+    fn to_json(&self) -> JsonValue {
+        json!(self)
+    }
 }
 
 impl<'a> ModuleConfig<'a> for f64 {
     fn from_config(config: &Value) -> Option<Self> {
         config.as_float()
     }
+
+    fn validate(config: &'a Value, _palette: &Palette) -> Vec<ConfigDiagnostic> {
+        expect_type(config, "float")
+    }
+
+    fn schema() -> JsonValue {
+        json!({ "type": "number" })
+    }
+
+    fn to_json(&self) -> JsonValue {
+        json!(self)
+    }
 }
 
 impl<'a> ModuleConfig<'a> for usize {
@@ -113,6 +465,18 @@ impl<'a> ModuleConfig<'a> for usize {
             _ => None,
         }
     }
+
+    fn validate(config: &'a Value, _palette: &Palette) -> Vec<ConfigDiagnostic> {
+        validate_positive_integer::<usize>(config)
+    }
+
+    fn schema() -> JsonValue {
+        positive_integer_schema()
+    }
+
+    fn to_json(&self) -> JsonValue {
+        json!(self)
+    }
 }
 
 impl<'a, T> ModuleConfig<'a> for Vec<T>
@@ -126,6 +490,29 @@ where
             .map(|value| T::from_config(value))
             .collect()
     }
+
+    fn validate(config: &'a Value, palette: &Palette) -> Vec<ConfigDiagnostic> {
+        match config.as_array() {
+            Some(array) => array
+                .iter()
+                .enumerate()
+                .flat_map(|(i, value)| {
+                    T::validate(value, palette)
+                        .into_iter()
+                        .map(move |diagnostic| diagnostic.in_key(&i.to_string()))
+                })
+                .collect(),
+            None => vec![ConfigDiagnostic::type_mismatch("array", config)],
+        }
+    }
+
+    fn schema() -> JsonValue {
+        json!({ "type": "array", "items": T::schema() })
+    }
+
+    fn to_json(&self) -> JsonValue {
+        JsonValue::Array(self.iter().map(T::to_json).collect())
+    }
 }
 
 impl<'a, T, S: ::std::hash::BuildHasher + Default> ModuleConfig<'a> for HashMap<String, T, S>
@@ -142,6 +529,32 @@ where
 
         Some(hm)
     }
+
+    fn validate(config: &'a Value, palette: &Palette) -> Vec<ConfigDiagnostic> {
+        match config.as_table() {
+            Some(table) => table
+                .iter()
+                .flat_map(|(key, value)| {
+                    T::validate(value, palette)
+                        .into_iter()
+                        .map(move |diagnostic| diagnostic.in_key(key))
+                })
+                .collect(),
+            None => vec![ConfigDiagnostic::type_mismatch("table", config)],
+        }
+    }
+
+    fn schema() -> JsonValue {
+        json!({ "type": "object", "additionalProperties": T::schema() })
+    }
+
+    fn to_json(&self) -> JsonValue {
+        JsonValue::Object(
+            self.iter()
+                .map(|(key, value)| (key.to_string(), value.to_json()))
+                .collect(),
+        )
+    }
 }
 
 impl<'a, T> ModuleConfig<'a> for Option<T>
@@ -151,61 +564,300 @@ where
     fn from_config(config: &'a Value) -> Option<Self> {
         Some(T::from_config(config))
     }
+
+    fn validate(config: &'a Value, palette: &Palette) -> Vec<ConfigDiagnostic> {
+        T::validate(config, palette)
+    }
+
+    fn schema() -> JsonValue {
+        T::schema()
+    }
+
+    fn to_json(&self) -> JsonValue {
+        self.as_ref().map_or(JsonValue::Null, T::to_json)
+    }
+}
+
+/// The colors of a palette, keyed by their lowercase name
+pub type Palette = HashMap<String, String>;
+
+/// The colors of the palette chosen with the `palette` option, which style strings can
+/// refer to by name. Styles are parsed without access to the root config, so it is set
+/// whenever a configuration is loaded. Validation doesn't use it, as it is given the
+/// palette to check against instead.
+static PALETTE: Lazy<RwLock<Palette>> = Lazy::new(Default::default);
+
+/// Get the colors of the palette chosen with the `palette` option of `config`, or `None`
+/// if no palette is chosen or it isn't defined in `palettes`
+pub fn get_palette(config: &Value) -> Option<Palette> {
+    let name = config.get("palette")?.as_str()?;
+    let palette = config.get("palettes")?.get(name)?.as_table();
+    if palette.is_none() {
+        log::warn!("The palette \"{}\" isn't defined in `palettes`", name);
+    }
+
+    let colors = palette?
+        .iter()
+        .filter_map(|(name, color)| Some((name.to_lowercase(), color.as_str()?.to_owned())))
+        .collect();
+    Some(colors)
+}
+
+/// Check that a color of a palette can be parsed. It can't refer to another palette color.
+pub fn validate_palette_color(color: &str) -> Vec<ConfigDiagnostic> {
+    match parse_plain_color_string(color) {
+        Some(_) => Vec::new(),
+        None => vec![ConfigDiagnostic::new(DiagnosticKind::InvalidColor(
+            color.to_owned(),
+        ))],
+    }
+}
+
+/// Use the palette chosen in `config`, if any, to parse style strings
+pub fn activate_palette(config: &Value) {
+    if let Some(palette) = get_palette(config) {
+        *PALETTE.write().unwrap() = palette;
+    }
 }
 
+/// The name of the directory-local configuration files
+pub const LOCAL_CONFIG_FILE: &str = ".starship.toml";
+
 /// Root config of starship.
+#[derive(Default)]
 pub struct StarshipConfig {
     pub config: Option<Value>,
+
+    /// The file which set each value of the config, keyed by its dotted path
+    /// (e.g. `directory.truncation_length`)
+    pub sources: BTreeMap<String, PathBuf>,
+
+    /// The files the config was merged from, in order, kept so that they can be checked
+    /// without being read again
+    pub files: Vec<ConfigFile>,
+}
+
+/// A configuration file, which is merged with the other files of the configuration
+pub struct ConfigFile {
+    pub path: PathBuf,
+    pub content: io::Result<String>,
 }
 
 impl StarshipConfig {
     /// Initialize the Config struct
     pub fn initialize() -> Self {
-        if let Some(file_data) = Self::config_from_file() {
-            StarshipConfig {
-                config: Some(file_data),
+        let mut config = StarshipConfig {
+            config: Some(Value::Table(Table::new())),
+            sources: BTreeMap::new(),
+            files: Vec::new(),
+        };
+
+        for file in Self::config_files() {
+            config.merge_file(file);
+        }
+
+        log::debug!("Config parsed: \n{:?}", &config.config);
+        for (key, source) in &config.sources {
+            log::debug!("Config value {} is set in {:?}", key, source);
+        }
+        if let Some(config) = &config.config {
+            activate_palette(config);
+        }
+        config
+    }
+
+    /// Deep merge a configuration file over the current config
+    fn merge_file(&mut self, file: ConfigFile) {
+        if let Some(mut layer) = Self::parse_file(&file) {
+            // Checking the config has a cost, so it is only done when the result will be shown
+            if log::log_enabled!(log::Level::Warn) {
+                // Styles can use the palette chosen in this file or in those merged before it
+                let layer = Value::Table(layer.clone());
+                let palette = get_palette(&layer)
+                    .or_else(|| self.config.as_ref().and_then(get_palette))
+                    .unwrap_or_default();
+                for diagnostic in config_check::check_config(&layer, &palette) {
+                    log::warn!("Problem in config file {:?}: {}", file.path, diagnostic);
+                }
             }
-        } else {
-            StarshipConfig {
-                config: Some(Value::Table(toml::value::Table::new())),
+
+            migrate_prompt_order(&mut layer);
+            if let Some(Value::Table(config)) = &mut self.config {
+                merge_tables(config, layer, "", &file.path, &mut self.sources);
+            }
+        }
+        self.files.push(file);
+    }
+
+    /// Parse a configuration file, or return `None` if it can't be read or parsed
+    fn parse_file(file: &ConfigFile) -> Option<Table> {
+        let content = match &file.content {
+            Ok(content) => {
+                log::trace!("Config file {:?} content: \n{}", file.path, content);
+                content
+            }
+            Err(e) => {
+                log::debug!("Unable to read config file {:?}: \n{}", file.path, e);
+                return None;
+            }
+        };
+
+        match toml::from_str::<Table>(content) {
+            Ok(layer) => Some(layer),
+            Err(e) => {
+                log::debug!("Unable to parse config file {:?}: \n{}", file.path, e);
+                None
+            }
+        }
+    }
+
+    /// Get the directories in which a `.starship.toml` file is used, from the
+    /// `trusted_directories` option. Their subdirectories are trusted as well.
+    pub fn trusted_directories(&self) -> Vec<PathBuf> {
+        let trusted = self
+            .config
+            .as_ref()
+            .and_then(|config| config.get("trusted_directories"))
+            .and_then(Value::as_array);
+
+        trusted
+            .map(|trusted| {
+                trusted
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .map(utils::expand_home)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Merge the `.starship.toml` files found walking up from `dir`, up to `repo_root` if
+    /// `dir` is in a repository. Files outside of the trusted directories are ignored, so
+    /// that e.g. a cloned repository can't change the prompt. The closest file is merged
+    /// last, so that it takes precedence.
+    pub fn merge_local_configs(&mut self, dir: &Path, repo_root: Option<&Path>) {
+        let trusted = self.trusted_directories();
+        if trusted.is_empty() {
+            return;
+        }
+
+        let mut paths = Vec::new();
+        for ancestor in dir.ancestors() {
+            let path = ancestor.join(LOCAL_CONFIG_FILE);
+            if path.is_file() {
+                if utils::is_in_directories(ancestor, &trusted) {
+                    paths.push(path);
+                } else {
+                    log::debug!("Ignoring {:?}, as it isn't in a trusted directory", path);
+                }
+            }
+
+            if Some(ancestor) == repo_root {
+                break;
+            }
+        }
+
+        for path in paths.into_iter().rev() {
+            log::debug!("Using the directory-local config file {:?}", path);
+            let content = utils::read_file(&path);
+            self.merge_file(ConfigFile { path, content });
+        }
+        if let Some(config) = &self.config {
+            activate_palette(config);
+        }
+    }
+
+    /// Read every configuration file, in the order in which they are merged: the files
+    /// included by the main file, the main file itself, then the files of `starship.d`
+    /// sorted by name (each after the files it includes).
+    pub fn config_files() -> Vec<ConfigFile> {
+        let mut files = Vec::new();
+        let mut visited = Vec::new();
+
+        if let Some(path) = Self::config_path() {
+            add_config_file(PathBuf::from(path), &mut files, &mut visited);
+        }
+
+        if let Some(dir) = Self::drop_in_dir() {
+            let mut paths = match fs::read_dir(&dir) {
+                Ok(entries) => entries
+                    .filter_map(Result::ok)
+                    .map(|entry| entry.path())
+                    .filter(|path| path.extension() == Some(OsStr::new("toml")))
+                    .collect::<Vec<PathBuf>>(),
+                Err(_) => Vec::new(),
+            };
+            paths.sort();
+
+            for path in paths {
+                add_config_file(path, &mut files, &mut visited);
             }
         }
+
+        files
     }
 
-    /// Create a config from a starship configuration file
-    fn config_from_file() -> Option<Value> {
-        let file_path = if let Ok(path) = env::var("STARSHIP_CONFIG") {
+    /// Get the path of the configuration file
+    pub fn config_path() -> Option<String> {
+        if let Ok(path) = env::var("STARSHIP_CONFIG") {
             // Use $STARSHIP_CONFIG as the config path if available
             log::debug!("STARSHIP_CONFIG is set: \n{}", &path);
-            path
+            Some(path)
         } else {
             // Default to using ~/.config/starship.toml
             log::debug!("STARSHIP_CONFIG is not set");
             let config_path = home_dir()?.join(".config/starship.toml");
             let config_path_str = config_path.to_str()?.to_owned();
             log::debug!("Using default config path: {}", config_path_str);
-            config_path_str
-        };
+            Some(config_path_str)
+        }
+    }
 
-        let toml_content = match utils::read_file(&file_path) {
-            Ok(content) => {
-                log::trace!("Config file content: \n{}", &content);
-                Some(content)
-            }
-            Err(e) => {
-                log::debug!("Unable to read config file content: \n{}", &e);
-                None
-            }
-        }?;
+    /// Get the directory of the files which are merged over the main configuration file.
+    /// This is `starship.d` next to `$STARSHIP_CONFIG` if it is set, or
+    /// `$XDG_CONFIG_HOME/starship.d` (`~/.config/starship.d` by default) otherwise.
+    fn drop_in_dir() -> Option<PathBuf> {
+        if let Some(path) = env::var_os("STARSHIP_CONFIG") {
+            return Some(Path::new(&path).parent()?.join("starship.d"));
+        }
 
-        let config = toml::from_str(&toml_content).ok()?;
-        log::debug!("Config parsed: \n{:?}", &config);
-        Some(config)
+        let config_home = match env::var_os("XDG_CONFIG_HOME") {
+            Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => home_dir()?.join(".config"),
+        };
+        Some(config_home.join("starship.d"))
     }
 
-    /// Get the subset of the table for a module by its name
+    /// Get the names of the custom modules defined in the `custom` table, such as
+    /// `custom.deploy`, sorted by name
+    pub fn custom_module_names(&self) -> Vec<String> {
+        let custom = self
+            .config
+            .as_ref()
+            .and_then(|config| config.get("custom"))
+            .and_then(Value::as_table);
+
+        let mut names = custom
+            .map(|custom| {
+                custom
+                    .iter()
+                    .filter(|(_, config)| config.is_table())
+                    .map(|(name, _)| format!("custom.{}", name))
+                    .collect::<Vec<String>>()
+            })
+            .unwrap_or_default();
+        names.sort();
+        names
+    }
+
+    /// Get the subset of the table for a module by its name. The config of a custom
+    /// module is found in the `custom` table.
     pub fn get_module_config(&self, module_name: &str) -> Option<&Value> {
-        let module_config = self.config.as_ref()?.as_table()?.get(module_name);
+        let table = self.config.as_ref()?.as_table()?;
+        let module_config = match module_name.strip_prefix("custom.") {
+            Some(custom_name) => table.get("custom")?.get(custom_name),
+            None => table.get(module_name),
+        };
         if module_config.is_some() {
             log::debug!(
                 "Config found for \"{}\": \n{:?}",
@@ -227,6 +879,115 @@ impl StarshipConfig {
     }
 }
 
+/// Add a configuration file to `files`, after the files it includes. Files which were
+/// already added are skipped, so that include cycles terminate.
+fn add_config_file(path: PathBuf, files: &mut Vec<ConfigFile>, visited: &mut Vec<PathBuf>) {
+    let key = fs::canonicalize(&path).unwrap_or_else(|_| path.clone());
+    if visited.contains(&key) {
+        return;
+    }
+    visited.push(key);
+
+    let content = utils::read_file(&path);
+    let includes = content
+        .as_ref()
+        .ok()
+        .and_then(|content| toml::from_str::<Table>(content).ok())
+        .and_then(|config| config.get("include").cloned());
+
+    if let Some(Value::Array(includes)) = includes {
+        for include in includes.iter().filter_map(Value::as_str) {
+            add_config_file(resolve_include(&path, include), files, visited);
+        }
+    }
+
+    files.push(ConfigFile { path, content });
+}
+
+/// Resolve the path of an included file, which may start with `~` or be relative to the
+/// directory of the file including it
+fn resolve_include(including_file: &Path, include: &str) -> PathBuf {
+    if include == "~" || include.starts_with("~/") {
+        return utils::expand_home(include);
+    }
+
+    match including_file.parent() {
+        Some(dir) => dir.join(include),
+        None => PathBuf::from(include),
+    }
+}
+
+/// Convert the `prompt_order` of a file, which listed the modules of the prompt before
+/// the root `format` replaced it, into the equivalent format. A `format` set in the same
+/// file takes precedence.
+fn migrate_prompt_order(layer: &mut Table) {
+    let prompt_order = match layer.remove("prompt_order") {
+        Some(Value::Array(prompt_order)) => prompt_order,
+        _ => return,
+    };
+    if layer.contains_key("format") {
+        return;
+    }
+
+    let format = prompt_order
+        .iter()
+        .filter_map(Value::as_str)
+        .map(|module| format!("${}", module))
+        .collect::<String>();
+    log::debug!("Using the format {:?} for the legacy prompt_order", format);
+    layer.insert("format".to_string(), Value::String(format));
+}
+
+/// Deep merge `layer` into `base`: tables are merged key by key, and any other value
+/// replaces the previous one. `sources` is updated with the file of each value.
+fn merge_tables(
+    base: &mut Table,
+    layer: Table,
+    prefix: &str,
+    source: &Path,
+    sources: &mut BTreeMap<String, PathBuf>,
+) {
+    for (key, value) in layer {
+        let path = format!("{}{}", prefix, key);
+        match (base.get_mut(&key), value) {
+            (Some(Value::Table(base_table)), Value::Table(layer_table)) => {
+                merge_tables(
+                    base_table,
+                    layer_table,
+                    &format!("{}.", path),
+                    source,
+                    sources,
+                );
+            }
+            (_, value) => {
+                // Forget the sources of the values which are replaced
+                let nested_prefix = format!("{}.", path);
+                sources.retain(|key, _| *key != path && !key.starts_with(&nested_prefix));
+                record_sources(&value, &path, source, sources);
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+fn record_sources(
+    value: &Value,
+    path: &str,
+    source: &Path,
+    sources: &mut BTreeMap<String, PathBuf>,
+) {
+    match value {
+        Value::Table(table) => {
+            for (key, value) in table {
+                record_sources(value, &format!("{}.{}", path, key), source, sources);
+            }
+        }
+        _ => {
+            sources.insert(path.to_string(), source.to_path_buf());
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct SegmentConfig<'a> {
     pub value: &'a str,
@@ -234,6 +995,7 @@ pub struct SegmentConfig<'a> {
 }
 
 impl<'a> ModuleConfig<'a> for SegmentConfig<'a> {
+    const KNOWN_FIELDS: &'static [&'static str] = &["value", "style"];
     fn from_config(config: &'a Value) -> Option<Self> {
         match config {
             Value::String(ref config_str) => Some(Self {
@@ -266,6 +1028,51 @@ impl<'a> ModuleConfig<'a> for SegmentConfig<'a> {
         };
         new_config
     }
+
+    fn validate(config: &'a Value, palette: &Palette) -> Vec<ConfigDiagnostic> {
+        match config {
+            Value::String(_) => Vec::new(),
+            Value::Table(table) => table
+                .iter()
+                .flat_map(|(key, value)| {
+                    match key.as_str() {
+                        "value" => <&str>::validate(value, palette),
+                        "style" => <Style>::validate(value, palette),
+                        _ => vec![ConfigDiagnostic::unknown_key(key, Self::KNOWN_FIELDS)],
+                    }
+                    .into_iter()
+                    .map(move |diagnostic| match diagnostic.kind {
+                        DiagnosticKind::UnknownKey { .. } => diagnostic,
+                        _ => diagnostic.in_key(key),
+                    })
+                })
+                .collect(),
+            _ => vec![ConfigDiagnostic::type_mismatch("string or table", config)],
+        }
+    }
+
+    fn schema() -> JsonValue {
+        json!({
+            "anyOf": [
+                { "type": "string" },
+                {
+                    "type": "object",
+                    "properties": {
+                        "value": <&str>::schema(),
+                        "style": <Style>::schema(),
+                    },
+                    "additionalProperties": false,
+                },
+            ]
+        })
+    }
+
+    fn to_json(&self) -> JsonValue {
+        match self.style {
+            Some(style) => json!({ "value": self.value, "style": style_to_string(&style) }),
+            None => json!(self.value),
+        }
+    }
 }
 
 impl<'a> SegmentConfig<'a> {
@@ -305,7 +1112,12 @@ impl Default for SegmentConfig<'static> {
  - 'italic'
  - '<color>'        (see the parse_color_string doc for valid color strings)
 */
-fn parse_style_string(style_string: &str) -> Option<ansi_term::Style> {
+pub fn parse_style_string(style_string: &str) -> Option<ansi_term::Style> {
+    parse_style_with_palette(style_string, &PALETTE.read().unwrap())
+}
+
+/// Parse a style string, where colors can be named after those of `palette`
+fn parse_style_with_palette(style_string: &str, palette: &Palette) -> Option<ansi_term::Style> {
     style_string
         .split_whitespace()
         .fold(Some(ansi_term::Style::new()), |maybe_style, token| {
@@ -330,35 +1142,58 @@ fn parse_style_string(style_string: &str) -> Option<ansi_term::Style> {
                     "none" => None,
 
                     // Try to see if this token parses as a valid color string
-                    color_string => parse_color_string(color_string).map(|ansi_color| {
-                        if col_fg {
-                            style.fg(ansi_color)
-                        } else {
-                            style.on(ansi_color)
-                        }
-                    }),
+                    color_string => {
+                        parse_color_or_palette_name(color_string, palette).map(|ansi_color| {
+                            if col_fg {
+                                style.fg(ansi_color)
+                            } else {
+                                style.on(ansi_color)
+                            }
+                        })
+                    }
                 }
             })
         })
 }
 
 /** Parse a string that represents a color setting, returning None if this fails
- There are three valid color formats:
+ There are four valid color formats:
   - #RRGGBB      (a hash followed by an RGB hex)
   - u8           (a number from 0-255, representing an ANSI color)
   - colstring    (one of the 16 predefined color strings)
+  - name         (the name of a color of the active palette)
 */
 fn parse_color_string(color_string: &str) -> Option<ansi_term::Color> {
+    parse_color_or_palette_name(color_string, &PALETTE.read().unwrap())
+}
+
+/// Parse a color string, which can also be the name of a color of `palette`
+fn parse_color_or_palette_name(
+    color_string: &str,
+    palette: &HashMap<String, String>,
+) -> Option<ansi_term::Color> {
+    parse_plain_color_string(color_string).or_else(|| {
+        let color = palette.get(color_string)?;
+        log::trace!("Read palette color {}: {}", color_string, color);
+        parse_plain_color_string(color)
+    })
+}
+
+fn parse_plain_color_string(color_string: &str) -> Option<ansi_term::Color> {
     // Parse RGB hex values
     log::trace!("Parsing color_string: {}", color_string);
-    if color_string.starts_with('#') {
+    if let Some(hex) = color_string.strip_prefix('#') {
         log::trace!(
             "Attempting to read hexadecimal color string: {}",
             color_string
         );
-        let r: u8 = u8::from_str_radix(&color_string[1..3], 16).ok()?;
-        let g: u8 = u8::from_str_radix(&color_string[3..5], 16).ok()?;
-        let b: u8 = u8::from_str_radix(&color_string[5..7], 16).ok()?;
+        // Only `#RRGGBB` is valid, and slicing anything else could panic
+        if hex.len() != 6 || !hex.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+            return None;
+        }
+        let r: u8 = u8::from_str_radix(&hex[0..2], 16).ok()?;
+        let g: u8 = u8::from_str_radix(&hex[2..4], 16).ok()?;
+        let b: u8 = u8::from_str_radix(&hex[4..6], 16).ok()?;
         log::trace!("Read RGB color string: {},{},{}", r, g, b);
         return Some(Color::RGB(r, g, b));
     }
@@ -399,6 +1234,56 @@ fn parse_color_string(color_string: &str) -> Option<ansi_term::Color> {
     predefined_color
 }
 
+/// Convert a style back into a style string, which can be parsed by `parse_style_string`
+pub fn style_to_string(style: &Style) -> String {
+    let mut tokens = Vec::new();
+
+    if style.is_bold {
+        tokens.push("bold".to_string());
+    }
+    if style.is_italic {
+        tokens.push("italic".to_string());
+    }
+    if style.is_underline {
+        tokens.push("underline".to_string());
+    }
+    if style.is_dimmed {
+        tokens.push("dimmed".to_string());
+    }
+    if let Some(color) = style.foreground {
+        tokens.push(format!("fg:{}", color_to_string(color)));
+    }
+    if let Some(color) = style.background {
+        tokens.push(format!("bg:{}", color_to_string(color)));
+    }
+
+    tokens.join(" ")
+}
+
+/// Convert a color back into a color string, the reverse of `parse_color_string`
+fn color_to_string(color: Color) -> String {
+    match color {
+        Color::Black => "black".to_string(),
+        Color::Red => "red".to_string(),
+        Color::Green => "green".to_string(),
+        Color::Yellow => "yellow".to_string(),
+        Color::Blue => "blue".to_string(),
+        Color::Purple => "purple".to_string(),
+        Color::Cyan => "cyan".to_string(),
+        Color::White => "white".to_string(),
+        Color::Fixed(8) => "bright-black".to_string(),
+        Color::Fixed(9) => "bright-red".to_string(),
+        Color::Fixed(10) => "bright-green".to_string(),
+        Color::Fixed(11) => "bright-yellow".to_string(),
+        Color::Fixed(12) => "bright-blue".to_string(),
+        Color::Fixed(13) => "bright-purple".to_string(),
+        Color::Fixed(14) => "bright-cyan".to_string(),
+        Color::Fixed(15) => "bright-white".to_string(),
+        Color::Fixed(num) => num.to_string(),
+        Color::RGB(r, g, b) => format!("#{:02x}{:02x}{:02x}", r, g, b),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -478,6 +1363,250 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_validate_config() {
+        #[allow(dead_code)]
+        #[derive(Clone, ModuleConfig)]
+        struct TestConfig<'a> {
+            pub symbol: &'a str,
+            pub style: Style,
+            pub modified: SegmentConfig<'a>,
+            pub some_array: Vec<Style>,
+            pub timeout: u64,
+        }
+
+        let config = toml::toml! {
+            symbol = 1
+            timeout = -5
+            style = "bold none"
+            modified = { value = "!", style = "rde", stlye = "red" }
+            some_array = ["red", "bleu"]
+            simbol = "T "
+        };
+        let mut diagnostics = TestConfig::validate(&config, &Palette::new())
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<String>>();
+        diagnostics.sort();
+
+        assert_eq!(
+            diagnostics,
+            vec![
+                "modified.stlye: unknown key, did you mean `style`?",
+                "modified.style: invalid style string \"rde\"",
+                "simbol: unknown key, did you mean `symbol`?",
+                "some_array.1: invalid style string \"bleu\"",
+                "symbol: expected a string, found integer",
+                "timeout: -5 is out of range, the value must be at least 1",
+            ]
+        );
+        assert_eq!(
+            TestConfig::KNOWN_FIELDS,
+            &["symbol", "style", "modified", "some_array", "timeout"]
+        );
+        assert_eq!(
+            TestConfig::validate(&Value::from(1), &Palette::new()),
+            vec![ConfigDiagnostic::new(DiagnosticKind::TypeMismatch {
+                expected: "table",
+                actual: "integer"
+            })]
+        );
+    }
+
+    #[test]
+    fn test_merge_tables() {
+        let mut config = Table::new();
+        let mut sources = BTreeMap::new();
+        let team = Path::new("team.toml");
+        let local = Path::new("local.toml");
+
+        let team_config = toml::toml! {
+            add_newline = false
+            [directory]
+            truncation_length = 1
+            style = "red"
+            [git_status]
+            ahead = { value = "up", style = "green" }
+        };
+        let local_config = toml::toml! {
+            [directory]
+            truncation_length = 2
+            [git_status]
+            ahead = "^"
+        };
+        merge_tables(
+            &mut config,
+            team_config.as_table().unwrap().clone(),
+            "",
+            team,
+            &mut sources,
+        );
+        merge_tables(
+            &mut config,
+            local_config.as_table().unwrap().clone(),
+            "",
+            local,
+            &mut sources,
+        );
+
+        assert_eq!(
+            Value::Table(config),
+            toml::toml! {
+                add_newline = false
+                [directory]
+                truncation_length = 2
+                style = "red"
+                [git_status]
+                ahead = "^"
+            }
+        );
+        let sources = sources
+            .iter()
+            .map(|(key, path)| (key.as_str(), path.to_str().unwrap()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            sources,
+            vec![
+                ("add_newline", "team.toml"),
+                ("directory.style", "team.toml"),
+                ("directory.truncation_length", "local.toml"),
+                ("git_status.ahead", "local.toml"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_config_file_includes() -> io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let main = dir.path().join("starship.toml");
+        fs::write(&main, "include = [\"team.toml\", \"missing.toml\"]")?;
+        // A cycle back to the main file is ignored
+        fs::write(
+            dir.path().join("team.toml"),
+            "include = [\"starship.toml\"]",
+        )?;
+
+        let mut files = Vec::new();
+        add_config_file(main, &mut files, &mut Vec::new());
+        let files = files
+            .iter()
+            .map(|file| {
+                let name = file.path.file_name().unwrap().to_str().unwrap();
+                (name.to_string(), file.content.is_ok())
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            files,
+            vec![
+                ("team.toml".to_string(), true),
+                ("missing.toml".to_string(), false),
+                ("starship.toml".to_string(), true),
+            ]
+        );
+        dir.close()
+    }
+
+    #[test]
+    fn test_merge_local_configs() -> io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let repo = dir.path().join("repo");
+        let sub = repo.join("sub");
+        fs::create_dir_all(&sub)?;
+        // Files above the repository root are ignored
+        fs::write(dir.path().join(LOCAL_CONFIG_FILE), "[jobs]\nsymbol = \"x\"")?;
+        fs::write(
+            repo.join(LOCAL_CONFIG_FILE),
+            "[package]\ndisabled = true\n[directory]\ntruncation_length = 1",
+        )?;
+        fs::write(
+            sub.join(LOCAL_CONFIG_FILE),
+            "[directory]\ntruncation_length = 2",
+        )?;
+
+        let trusted = dir.path().to_str().unwrap();
+        let mut config = StarshipConfig {
+            config: Some(toml::toml! { trusted_directories = [trusted] }),
+            ..StarshipConfig::default()
+        };
+        config.merge_local_configs(&sub, Some(&repo));
+
+        assert_eq!(
+            config.get_module_config("package"),
+            Some(&toml::toml! { disabled = true })
+        );
+        assert_eq!(
+            config.get_module_config("directory"),
+            Some(&toml::toml! { truncation_length = 2 })
+        );
+        assert_eq!(config.get_module_config("jobs"), None);
+        assert_eq!(
+            config.sources.get("directory.truncation_length"),
+            Some(&sub.join(LOCAL_CONFIG_FILE))
+        );
+        dir.close()
+    }
+
+    #[test]
+    fn test_untrusted_local_configs_are_ignored() -> io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let trusted = tempfile::tempdir()?;
+        fs::write(
+            dir.path().join(LOCAL_CONFIG_FILE),
+            "[package]\ndisabled = true",
+        )?;
+
+        // Nothing is trusted by default
+        let mut config = StarshipConfig {
+            config: Some(Value::Table(Table::new())),
+            ..StarshipConfig::default()
+        };
+        config.merge_local_configs(dir.path(), None);
+        assert_eq!(config.get_module_config("package"), None);
+
+        let trusted_dir = trusted.path().to_str().unwrap();
+        let mut config = StarshipConfig {
+            config: Some(toml::toml! { trusted_directories = [trusted_dir] }),
+            ..StarshipConfig::default()
+        };
+        config.merge_local_configs(dir.path(), None);
+        assert_eq!(config.get_module_config("package"), None);
+
+        trusted.close()?;
+        dir.close()
+    }
+
+    #[test]
+    fn test_resolve_include() {
+        let including_file = Path::new("/config/starship.toml");
+
+        assert_eq!(
+            resolve_include(including_file, "team.toml"),
+            PathBuf::from("/config/team.toml")
+        );
+        assert_eq!(
+            resolve_include(including_file, "/etc/starship.toml"),
+            PathBuf::from("/etc/starship.toml")
+        );
+        assert_eq!(
+            resolve_include(including_file, "~/team.toml"),
+            home_dir().unwrap().join("team.toml")
+        );
+    }
+
+    #[test]
+    fn test_closest_match() {
+        let candidates = &["format", "disabled", "truncation_length"];
+
+        assert_eq!(closest_match("fromat", candidates), Some("format"));
+        assert_eq!(closest_match("disable", candidates), Some("disabled"));
+        assert_eq!(
+            closest_match("truncation_lenght", candidates),
+            Some("truncation_length")
+        );
+        assert_eq!(closest_match("style", candidates), None);
+    }
+
     #[test]
     fn test_load_optional_config() {
         #[derive(Clone, ModuleConfig)]
@@ -606,6 +1735,11 @@ mod tests {
         let config = Value::from("djklgfhjkldhlhk;j");
         assert!(<Style>::from_config(&config).is_none());
 
+        // Test hex colors which aren't `#RRGGBB`
+        for color in &["#fff", "#ééé", "#12345", "#1234567", "#+f+f+f"] {
+            assert!(<Style>::from_config(&Value::from(*color)).is_none());
+        }
+
         // Test a string that's nullified by `none`
         let config = Value::from("fg:red bg:green bold none");
         assert!(<Style>::from_config(&config).is_none());
@@ -636,4 +1770,81 @@ mod tests {
             Style::new().fg(Color::Fixed(125)).on(Color::Fixed(127))
         );
     }
+
+    #[test]
+    fn test_palette_colors() {
+        let config = toml::toml! {
+            palette = "dracula"
+
+            [palettes.dracula]
+            Accent = "#8be9fd"
+            warning = "214"
+            red = "#ff5555"
+            nested = "accent"
+            short = "#fff"
+
+            [palettes.other]
+            accent = "blue"
+        };
+        let palette = get_palette(&config).unwrap();
+
+        let parse = |color| parse_color_or_palette_name(color, &palette);
+        assert_eq!(parse("accent"), Some(Color::RGB(0x8b, 0xe9, 0xfd)));
+        assert_eq!(parse("warning"), Some(Color::Fixed(214)));
+        // Predefined colors can't be overridden, and palette colors can't refer to each other
+        assert_eq!(parse("red"), Some(Color::Red));
+        assert_eq!(parse("nested"), None);
+        assert_eq!(parse("short"), None);
+        assert_eq!(parse("missing"), None);
+        assert!(parse_style_with_palette("fg:short", &palette).is_none());
+        assert_eq!(validate_palette_color("#fff").len(), 1);
+
+        let config = toml::toml! {
+            palette = "missing"
+            [palettes.other]
+            accent = "blue"
+        };
+        assert!(get_palette(&config).is_none());
+    }
+
+    #[test]
+    fn test_migrate_prompt_order() {
+        let mut layer = toml::toml! {
+            add_newline = false
+            prompt_order = ["directory", "line_break", "character"]
+        };
+        migrate_prompt_order(layer.as_table_mut().unwrap());
+        assert_eq!(
+            layer,
+            toml::toml! {
+                add_newline = false
+                format = "$directory$line_break$character"
+            }
+        );
+
+        let mut layer = toml::toml! {
+            format = "$all"
+            prompt_order = ["character"]
+        };
+        migrate_prompt_order(layer.as_table_mut().unwrap());
+        assert_eq!(layer, toml::toml! { format = "$all" });
+    }
+
+    #[test]
+    fn test_style_to_string() {
+        let style = Style::new()
+            .bold()
+            .underline()
+            .fg(Color::Fixed(8))
+            .on(Color::RGB(5, 5, 5));
+        let style_string = style_to_string(&style);
+        assert_eq!(style_string, "bold underline fg:bright-black bg:#050505");
+        assert_eq!(parse_style_string(&style_string), Some(style));
+
+        assert_eq!(
+            style_to_string(&Style::new().fg(Color::Fixed(120))),
+            "fg:120"
+        );
+        assert_eq!(style_to_string(&Style::new()), "");
+    }
 }
@@ -0,0 +1,669 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::Duration;
+
+use ansi_term::Color;
+use toml::value::Table;
+use toml::Value;
+
+use crate::cache;
+use crate::config::{
+    self, closest_match, validate_style, ConfigDiagnostic, ConfigFile, DiagnosticKind,
+    ModuleConfig, Palette, StarshipConfig,
+};
+use crate::configs::conditions::ConditionsConfig;
+use crate::configs::time::is_legacy_format;
+use crate::configs::{self, StarshipRootConfig};
+use crate::formatter::{self, FormatElement, StyleElement};
+use crate::module::ALL_MODULES;
+
+/// Options which are read for every module, besides those of the module's own config
+pub const COMMON_MODULE_KEYS: &[&str] = &[
+    "format",
+    "disabled",
+    "command_timeout",
+    "when",
+    "only_if",
+    "not_in",
+    "background",
+];
+
+/// Options of the root config which hold format strings
+const ROOT_FORMAT_KEYS: &[&str] = &[
+    "format",
+    "right_format",
+    "transient_format",
+    "continuation_prompt",
+];
+
+/// Check the contents of a configuration file, whose style strings can use the colors of
+/// `palette`
+pub fn check_config_str(content: &str, palette: &Palette) -> Vec<ConfigDiagnostic> {
+    match toml::from_str::<Value>(content) {
+        Ok(config) => check_config(&config, palette),
+        Err(error) => vec![ConfigDiagnostic::new(DiagnosticKind::Syntax(
+            error.to_string(),
+        ))],
+    }
+}
+
+/// Check a parsed configuration, with a diagnostic for each problem found. The palette
+/// which style strings can use is given, as it may be chosen by another file of the
+/// configuration.
+pub fn check_config(config: &Value, palette: &Palette) -> Vec<ConfigDiagnostic> {
+    let table = match config.as_table() {
+        Some(table) => table,
+        None => return vec![ConfigDiagnostic::new(DiagnosticKind::InvalidValue)],
+    };
+
+    let mut diagnostics = Vec::new();
+    let mut root_table = Table::new();
+    for (key, value) in table.iter() {
+        if let Some(module_diagnostics) = check_module(key, value, palette) {
+            diagnostics.extend(
+                module_diagnostics
+                    .into_iter()
+                    .map(|diagnostic| diagnostic.in_key(key)),
+            );
+        } else if key == "prompt_order" {
+            // `prompt_order` is converted to a format, see `config::migrate_prompt_order`
+            diagnostics.push(
+                ConfigDiagnostic::new(DiagnosticKind::Deprecated {
+                    replacement: "format",
+                })
+                .in_key(key),
+            );
+        } else if value.is_table() && !StarshipRootConfig::KNOWN_FIELDS.contains(&key.as_str()) {
+            let suggestion = closest_match(key, ALL_MODULES).map(str::to_owned);
+            diagnostics.push(
+                ConfigDiagnostic::new(DiagnosticKind::UnknownModule { suggestion }).in_key(key),
+            );
+        } else {
+            root_table.insert(key.clone(), value.clone());
+        }
+    }
+
+    for key in ROOT_FORMAT_KEYS {
+        if let Some(format) = root_table.get(*key).and_then(Value::as_str) {
+            diagnostics.extend(
+                check_format(format, palette)
+                    .into_iter()
+                    .map(|diagnostic| diagnostic.in_key(key)),
+            );
+        }
+    }
+    diagnostics.extend(check_palettes(&root_table));
+    diagnostics.extend(StarshipRootConfig::validate(
+        &Value::Table(root_table),
+        palette,
+    ));
+
+    diagnostics
+}
+
+/// Check that the chosen palette is defined, and that the palettes hold valid colors
+fn check_palettes(root_table: &Table) -> Vec<ConfigDiagnostic> {
+    let palettes = root_table.get("palettes").and_then(Value::as_table);
+    let mut diagnostics = palettes
+        .into_iter()
+        .flatten()
+        .flat_map(|(palette, colors)| {
+            colors
+                .as_table()
+                .into_iter()
+                .flatten()
+                .filter_map(|(name, color)| Some((name, color.as_str()?)))
+                .flat_map(move |(name, color)| {
+                    config::validate_palette_color(color)
+                        .into_iter()
+                        .map(move |diagnostic| diagnostic.in_key(name).in_key(palette))
+                })
+        })
+        .map(|diagnostic| diagnostic.in_key("palettes"))
+        .collect::<Vec<ConfigDiagnostic>>();
+
+    if let Some(palette) = root_table.get("palette").and_then(Value::as_str) {
+        let names = palettes
+            .map(|palettes| palettes.keys().map(String::as_str).collect::<Vec<&str>>())
+            .unwrap_or_default();
+        if !names.contains(&palette) {
+            let suggestion = closest_match(palette, &names).map(str::to_owned);
+            diagnostics.push(
+                ConfigDiagnostic::new(DiagnosticKind::UnknownPalette { suggestion })
+                    .in_key("palette"),
+            );
+        }
+    }
+    diagnostics
+}
+
+/// Check the config table of a module, or return `None` if there is no module named `name`
+fn check_module(name: &str, config: &Value, palette: &Palette) -> Option<Vec<ConfigDiagnostic>> {
+    if name == "custom" {
+        return Some(check_custom_modules(config, palette));
+    }
+    let table = match config.as_table() {
+        Some(table) => table,
+        None => return configs::validate_module(name, config, palette),
+    };
+
+    // The common options are checked here, as they aren't part of the module's own config
+    let mut module_table = table.clone();
+    let mut common_diagnostics = Vec::new();
+    for key in COMMON_MODULE_KEYS {
+        // The `when` option of a custom module can also be a command, which is part of its
+        // own config
+        let is_custom_command = *key == "when"
+            && name.starts_with("custom.")
+            && table.get("when").map(Value::is_str) == Some(true);
+        if is_custom_command {
+            continue;
+        }
+        if let Some(value) = module_table.remove(*key) {
+            // The `format` of the time module used to be its strftime format
+            let is_legacy_time_format = name == "time"
+                && *key == "format"
+                && matches!(value.as_str(), Some(format) if is_legacy_format(format));
+            if is_legacy_time_format {
+                common_diagnostics.push(
+                    ConfigDiagnostic::new(DiagnosticKind::Deprecated {
+                        replacement: "time_format",
+                    })
+                    .in_key(key),
+                );
+                continue;
+            }
+            common_diagnostics.extend(
+                check_common_key(key, &value, palette)
+                    .into_iter()
+                    .map(|diagnostic| diagnostic.in_key(key)),
+            );
+        }
+    }
+
+    let mut diagnostics = configs::validate_module(name, &Value::Table(module_table), palette)?;
+    for diagnostic in diagnostics.iter_mut() {
+        if let DiagnosticKind::UnknownKey { suggestion } = &mut diagnostic.kind {
+            if suggestion.is_none() && diagnostic.path.len() == 1 {
+                *suggestion =
+                    closest_match(&diagnostic.path[0], COMMON_MODULE_KEYS).map(str::to_owned);
+            }
+        }
+    }
+    diagnostics.extend(common_diagnostics);
+    Some(diagnostics)
+}
+
+/// Check each custom module of the `custom` table
+fn check_custom_modules(config: &Value, palette: &Palette) -> Vec<ConfigDiagnostic> {
+    let table = match config.as_table() {
+        Some(table) => table,
+        None => return vec![ConfigDiagnostic::type_mismatch("table", config)],
+    };
+
+    table
+        .iter()
+        .flat_map(|(name, config)| {
+            let diagnostics = match config {
+                Value::Table(_) => check_module(&format!("custom.{}", name), config, palette),
+                _ => Some(vec![ConfigDiagnostic::type_mismatch("table", config)]),
+            };
+            diagnostics
+                .unwrap_or_default()
+                .into_iter()
+                .map(move |diagnostic| diagnostic.in_key(name))
+        })
+        .collect()
+}
+
+fn check_common_key(key: &str, value: &Value, palette: &Palette) -> Vec<ConfigDiagnostic> {
+    match key {
+        "format" => match value.as_str() {
+            Some(format) => check_format(format, palette),
+            None => vec![ConfigDiagnostic::type_mismatch("string", value)],
+        },
+        "disabled" => bool::validate(value, palette),
+        "command_timeout" => u64::validate(value, palette),
+        "when" | "only_if" => ConditionsConfig::validate(value, palette),
+        "not_in" => <Vec<&str>>::validate(value, palette),
+        "background" => Color::validate(value, palette),
+        _ => Vec::new(),
+    }
+}
+
+/// Check that a format string can be parsed, as well as the styles of its text groups
+fn check_format(format: &str, palette: &Palette) -> Vec<ConfigDiagnostic> {
+    fn check_elements(elements: &[FormatElement], palette: &Palette) -> Vec<ConfigDiagnostic> {
+        elements
+            .iter()
+            .flat_map(|element| match element {
+                FormatElement::TextGroup(group) => {
+                    let mut diagnostics = check_elements(&group.format, palette);
+                    if let StyleElement::Text(style) = group.style {
+                        diagnostics.extend(validate_style(style, palette));
+                    }
+                    diagnostics
+                }
+                FormatElement::Conditional(elements) => check_elements(elements, palette),
+                _ => Vec::new(),
+            })
+            .collect()
+    }
+
+    match formatter::parse(format) {
+        Ok(elements) => check_elements(&elements, palette),
+        Err(error) => vec![ConfigDiagnostic::new(DiagnosticKind::InvalidFormat(error))],
+    }
+}
+
+/// Check each configuration file on its own, against the palette of the whole
+/// configuration. A missing main file isn't a problem, as the defaults are used instead, so
+/// it is left out.
+fn check_files(
+    files: &[ConfigFile],
+    main_path: Option<&str>,
+    palette: &Palette,
+) -> Vec<(PathBuf, Vec<ConfigDiagnostic>)> {
+    files
+        .iter()
+        .filter_map(|file| {
+            let diagnostics = match &file.content {
+                Ok(content) => check_config_str(content, palette),
+                Err(error)
+                    if error.kind() == io::ErrorKind::NotFound
+                        && main_path.map(Path::new) == Some(file.path.as_path()) =>
+                {
+                    return None
+                }
+                Err(error) => vec![ConfigDiagnostic::new(DiagnosticKind::UnreadableFile(
+                    error.to_string(),
+                ))],
+            };
+            Some((file.path.clone(), diagnostics))
+        })
+        .collect()
+}
+
+/// Check the configuration files, printing every problem found
+pub fn check() {
+    let main_path = StarshipConfig::config_path();
+    let config = StarshipConfig::initialize();
+    let results = check_files(
+        &config.files,
+        main_path.as_deref(),
+        &merged_palette(&config),
+    );
+    if results.is_empty() {
+        match main_path {
+            Some(path) => println!(
+                "There is no configuration file at {}, so the defaults are used",
+                path
+            ),
+            None => println!("There is no configuration file, so the defaults are used"),
+        }
+        return;
+    }
+
+    let mut has_problems = false;
+    for (path, diagnostics) in results {
+        if diagnostics.is_empty() {
+            println!("No problems found in {}", path.display());
+            continue;
+        }
+
+        has_problems = true;
+        println!(
+            "Found {} problem(s) in {}:",
+            diagnostics.len(),
+            path.display()
+        );
+        for diagnostic in diagnostics {
+            println!("  {}", diagnostic);
+        }
+    }
+
+    if has_problems {
+        process::exit(1);
+    }
+}
+
+/// The palette chosen by the merged configuration, which the styles of every file are
+/// rendered with
+pub fn merged_palette(config: &StarshipConfig) -> Palette {
+    config
+        .config
+        .as_ref()
+        .and_then(config::get_palette)
+        .unwrap_or_default()
+}
+
+/// How long the marker of a shell session is kept, after which the session is likely over
+const SESSION_MARKER_MAX_AGE: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// Print a warning if the files of the configuration have problems. This is only done once
+/// for each version of the configuration in each shell session, to not repeat it on every
+/// prompt.
+pub fn warn_once_per_session(config: &StarshipConfig) {
+    let session_key = match env::var("STARSHIP_SESSION_KEY") {
+        Ok(key) if !key.is_empty() => key,
+        _ => return,
+    };
+    let sessions_dir = match cache::cache_dir() {
+        Some(dir) => dir.join("sessions"),
+        None => return,
+    };
+
+    let mut version = String::new();
+    for file in &config.files {
+        if let Ok(content) = &file.content {
+            version.push_str(&file.path.to_string_lossy());
+            version.push('\0');
+            version.push_str(content);
+            version.push('\0');
+        }
+    }
+    if version.is_empty() {
+        return;
+    }
+
+    let marker = sessions_dir.join(format!("{}-{}", session_key, cache::hash(&version)));
+    if marker.exists() {
+        return;
+    }
+    prune_session_markers(&sessions_dir, &session_key, SESSION_MARKER_MAX_AGE);
+    if let Err(error) = fs::create_dir_all(&sessions_dir).and_then(|_| fs::write(&marker, "")) {
+        // Without the marker, the warning would be repeated on every prompt
+        log::debug!("Unable to write the session marker {:?}: {}", marker, error);
+        return;
+    }
+
+    let main_path = StarshipConfig::config_path();
+    let problems: usize = check_files(&config.files, main_path.as_deref(), &merged_palette(config))
+        .iter()
+        .map(|(_, diagnostics)| diagnostics.len())
+        .sum();
+    if problems > 0 {
+        eprintln!(
+            "[WARN] - Found {} problem(s) in the configuration. \
+             Run `starship config check` for details.",
+            problems
+        );
+    }
+}
+
+/// Remove the markers of this session for previous versions of the configuration, along
+/// with the markers older than `max_age`, so that the markers don't pile up
+fn prune_session_markers(sessions_dir: &Path, session_key: &str, max_age: Duration) {
+    let entries = match fs::read_dir(sessions_dir) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+
+    let session_prefix = format!("{}-", session_key);
+    for entry in entries.flatten() {
+        let is_this_session = entry
+            .file_name()
+            .to_string_lossy()
+            .starts_with(&session_prefix);
+        let age = entry
+            .metadata()
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok());
+        let is_stale = matches!(age, Some(age) if age >= max_age);
+
+        if is_this_session || is_stale {
+            if let Err(error) = fs::remove_file(entry.path()) {
+                log::debug!(
+                    "Unable to remove the session marker {:?}: {}",
+                    entry.path(),
+                    error
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Check a configuration on its own, with the palette it chooses
+    fn messages(content: &str) -> Vec<String> {
+        let palette = toml::from_str(content)
+            .ok()
+            .and_then(|config| config::get_palette(&config))
+            .unwrap_or_default();
+        check_config_str(content, &palette)
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn valid_config() {
+        let config = r#"
+            add_newline = false
+            format = "[$directory](bold blue)$character"
+
+            [directory]
+            format = "$path"
+            truncation_length = 2
+            style = "none"
+
+            [git_status.ahead]
+            value = "⇡"
+            style = "green"
+        "#;
+        assert_eq!(messages(config), Vec::<String>::new());
+    }
+
+    #[test]
+    fn syntax_error() {
+        let diagnostics = check_config_str("[directory\nstyle = 1", &Palette::new());
+        assert_eq!(diagnostics.len(), 1);
+        match &diagnostics[0].kind {
+            DiagnosticKind::Syntax(message) => assert!(message.contains("line 1")),
+            kind => panic!("unexpected diagnostic {:?}", kind),
+        }
+    }
+
+    #[test]
+    fn unknown_module() {
+        assert_eq!(
+            messages("[directroy]\ndisabled = true"),
+            vec!["directroy: unknown module, did you mean `directory`?"]
+        );
+        assert_eq!(messages("[foobar]"), vec!["foobar: unknown module"]);
+    }
+
+    #[test]
+    fn unknown_keys() {
+        assert_eq!(
+            messages("add_newlin = false\n[directory]\ntruncation_lenght = 1\nformt = \"\""),
+            vec![
+                "directory.formt: unknown key, did you mean `format`?",
+                "directory.truncation_lenght: unknown key, did you mean `truncation_length`?",
+                "add_newlin: unknown key, did you mean `add_newline`?",
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_values() {
+        assert_eq!(
+            messages(
+                "format = \"[$all](blod)\"\n\
+                 [directory]\nstyle = \"cyna\"\ndisabled = \"yes\"\n\
+                 [git_status.ahead]\nstyle = \"gren\"\nvaleu = \"\""
+            ),
+            vec![
+                "directory.style: invalid style string \"cyna\"",
+                "directory.disabled: expected a boolean, found string",
+                "git_status.ahead.style: invalid style string \"gren\"",
+                "git_status.ahead.valeu: unknown key, did you mean `value`?",
+                "format: invalid style string \"blod\"",
+            ]
+        );
+    }
+
+    #[test]
+    fn malformed_hex_colors() {
+        assert_eq!(
+            messages(
+                "[directory]\nstyle = \"#fff\"\n\
+                 [time]\nstyle = \"bg:#ééé\"\n\
+                 [character]\nformat = \"[❯](#+f+f+f)\""
+            ),
+            vec![
+                "character.format: invalid style string \"#+f+f+f\"",
+                "directory.style: invalid style string \"#fff\"",
+                "time.style: invalid style string \"bg:#ééé\"",
+            ]
+        );
+    }
+
+    #[test]
+    fn common_module_keys() {
+        assert_eq!(
+            messages("[rust]\ncommand_timeout = 0\nformat = 1\ndisabld = true"),
+            vec![
+                "rust.disabld: unknown key, did you mean `disabled`?",
+                "rust.format: expected a string, found integer",
+                "rust.command_timeout: 0 is out of range, the value must be at least 1",
+            ]
+        );
+    }
+
+    #[test]
+    fn legacy_time_format() {
+        assert_eq!(
+            messages("[time]\nformat = \"%T\""),
+            vec!["time.format: deprecated, use `time_format` instead"]
+        );
+        assert!(messages("[time]\nformat = \"at $time\"").is_empty());
+    }
+
+    #[test]
+    fn legacy_prompt_order() {
+        assert_eq!(
+            messages("prompt_order = [\"directory\", \"character\"]"),
+            vec!["prompt_order: deprecated, use `format` instead"]
+        );
+    }
+
+    #[test]
+    fn display_conditions() {
+        assert_eq!(
+            messages(
+                "[rust]\nnot_in = \"~/tmp\"\n\
+                 [rust.when]\nfiles = [\"Cargo.toml\"]\nssh = \"yes\"\nenvs = {}"
+            ),
+            vec![
+                "rust.when.envs: unknown key, did you mean `env`?",
+                "rust.when.ssh: expected a boolean, found string",
+                "rust.not_in: expected an array, found string",
+            ]
+        );
+    }
+
+    #[test]
+    fn powerline() {
+        assert_eq!(
+            messages(
+                "[powerline]\nenabled = \"yes\"\nbackground = \"gray\"\n\
+                 [rust]\nbackground = \"blu\""
+            ),
+            vec![
+                "rust.background: invalid color \"blu\"",
+                "powerline.background: invalid color \"gray\"",
+                "powerline.enabled: expected a boolean, found string",
+            ]
+        );
+    }
+
+    #[test]
+    fn custom_modules() {
+        assert_eq!(
+            messages(
+                "[custom.deploy]\ncommand = \"echo prod\"\nwhen = \"true\"\nfiels = []\n\
+                 [custom.vpn]\ncommand = 1\nwhen = { ssh = true }\n\
+                 [custom]\nbroken = \"echo\""
+            ),
+            vec![
+                "custom.broken: expected a table, found string",
+                "custom.deploy.fiels: unknown key, did you mean `files`?",
+                "custom.vpn.command: expected a string, found integer",
+            ]
+        );
+    }
+
+    #[test]
+    fn palettes() {
+        let config = r##"
+            palette = "dracula"
+
+            [palettes.dracula]
+            accent = "#8be9fd"
+            warning = "11"
+
+            [directory]
+            style = "bold fg:accent bg:WARNING"
+        "##;
+        assert_eq!(messages(config), Vec::<String>::new());
+
+        // A file which doesn't choose a palette uses the one of the whole configuration
+        let mut palette = Palette::new();
+        palette.insert("accent".to_string(), "#8be9fd".to_string());
+        let included = "[directory]\nstyle = \"fg:accent\"";
+        assert!(check_config_str(included, &palette).is_empty());
+        assert_eq!(messages(included).len(), 1);
+
+        assert_eq!(
+            messages(
+                "palette = \"drakula\"\n\
+                 [palettes.dracula]\ncyan = \"#8be9fd\"\nbroken = \"#zz\"\nshort = \"#fff\"\n\
+                 [time]\nstyle = \"fg:no_such_color\""
+            ),
+            vec![
+                "time.style: invalid style string \"fg:no_such_color\"",
+                "palettes.dracula.broken: invalid color \"#zz\"",
+                "palettes.dracula.short: invalid color \"#fff\"",
+                "palette: unknown palette, did you mean `dracula`?",
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_format() {
+        assert_eq!(
+            messages("[time]\nformat = \"[$time\""),
+            vec!["time.format: invalid format string: unexpected input at position 0: \"[$time\""]
+        );
+    }
+
+    #[test]
+    fn session_markers_are_pruned() -> io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        for marker in &["abc-1", "abc-2", "def-1"] {
+            fs::write(dir.path().join(marker), "")?;
+        }
+        let markers = || -> io::Result<Vec<String>> {
+            let mut markers = fs::read_dir(dir.path())?
+                .map(|entry| Ok(entry?.file_name().to_string_lossy().into_owned()))
+                .collect::<io::Result<Vec<String>>>()?;
+            markers.sort();
+            Ok(markers)
+        };
+
+        // The markers of other sessions are kept until they are stale
+        prune_session_markers(dir.path(), "abc", SESSION_MARKER_MAX_AGE);
+        assert_eq!(markers()?, vec!["def-1"]);
+        prune_session_markers(dir.path(), "abc", Duration::from_secs(0));
+        assert!(markers()?.is_empty());
+        dir.close()
+    }
+}
@@ -0,0 +1,387 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt::Write as FmtWrite;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use git2::Repository;
+use serde_json::Value as JsonValue;
+use toml::value::Table;
+use toml::Value;
+
+use crate::config::{ModuleConfig, Palette, StarshipConfig};
+use crate::config_check::{self, COMMON_MODULE_KEYS};
+use crate::configs;
+use crate::module::ALL_MODULES;
+
+/// The comment marking options which are set in the configuration file
+const OVERRIDE_MARKER: &str = "# overridden";
+
+/// The comment marking options which are set to a value which can't be used
+const INVALID_OVERRIDE_MARKER: &str = "# invalid override";
+
+/// Print the effective configuration of the given modules, or of the root config and every
+/// module if none are given. With `use_default`, the configuration file is ignored.
+pub fn print_config(modules: &[&str], use_default: bool) {
+    let config = if use_default {
+        StarshipConfig::default()
+    } else {
+        let mut config = StarshipConfig::initialize();
+        if let Ok(dir) = env::current_dir() {
+            let repo_root = Repository::discover(&dir)
+                .ok()
+                .and_then(|repo| repo.workdir().map(Path::to_path_buf));
+            config.merge_local_configs(&dir, repo_root.as_deref());
+        }
+        config
+    };
+
+    match render_config(&config, modules) {
+        Ok(output) => print!("{}", output),
+        Err(name) => {
+            eprintln!(
+                "Unknown module \"{}\", run `starship module --list` to see the supported modules",
+                name
+            );
+            process::exit(1);
+        }
+    }
+}
+
+/// Render the effective configuration as TOML, or return the name of an unknown module
+fn render_config(config: &StarshipConfig, modules: &[&str]) -> Result<String, String> {
+    let mut output = String::new();
+    if config.config.is_some() {
+        writeln!(
+            output,
+            "# Options set in a configuration file are marked with \"{} in <file>\"",
+            OVERRIDE_MARKER
+        )
+        .unwrap();
+    }
+
+    let custom_modules = config.custom_module_names();
+    let palette = config_check::merged_palette(config);
+    let modules = if modules.is_empty() {
+        let user_table = config.config.as_ref().and_then(Value::as_table);
+        if let Some(root) = json_to_toml(&config.get_root_config().to_json()) {
+            writeln!(output).unwrap();
+            render_toml_table(
+                &mut output,
+                &root,
+                None,
+                user_table,
+                &config.sources,
+                &palette,
+            );
+        }
+        ALL_MODULES
+            .iter()
+            .copied()
+            .chain(custom_modules.iter().map(String::as_str))
+            .collect()
+    } else {
+        modules.to_vec()
+    };
+
+    for name in modules {
+        let user_config = config.get_module_config(name);
+        // A custom module only exists if it is defined in the configuration
+        if name.starts_with("custom.") && user_config.is_none() {
+            return Err(name.to_string());
+        }
+        let resolved = configs::module_config(name, user_config).ok_or_else(|| name.to_string())?;
+        let mut resolved = json_to_toml(&resolved).unwrap_or_else(|| Value::Table(Table::new()));
+
+        // The common options are only part of the output if they are set
+        let user_table = user_config.and_then(Value::as_table);
+        if let (Some(table), Some(user_table)) = (resolved.as_table_mut(), user_table) {
+            for key in COMMON_MODULE_KEYS {
+                if let Some(value) = user_table.get(*key) {
+                    table
+                        .entry(key.to_string())
+                        .or_insert_with(|| value.clone());
+                }
+            }
+        }
+
+        let header = name.split('.').map(format_key).collect::<Vec<String>>();
+        writeln!(output, "\n[{}]", header.join(".")).unwrap();
+        render_toml_table(
+            &mut output,
+            &resolved,
+            Some(name),
+            user_table,
+            &config.sources,
+            &palette,
+        );
+    }
+
+    Ok(output)
+}
+
+/// Write each option of a table on its own line, marking those set in `user_table`
+fn render_toml_table(
+    output: &mut String,
+    config: &Value,
+    module: Option<&str>,
+    user_table: Option<&Table>,
+    sources: &BTreeMap<String, PathBuf>,
+    palette: &Palette,
+) {
+    let table = match config.as_table() {
+        Some(table) => table,
+        None => return,
+    };
+
+    for (key, value) in table {
+        write!(output, "{} = {}", format_key(key), format_value(value)).unwrap();
+        if let Some(user_value) = user_table.and_then(|user_table| user_table.get(key)) {
+            let is_valid = is_valid_override(module, key, user_value, palette);
+            let marker = if is_valid {
+                OVERRIDE_MARKER
+            } else {
+                INVALID_OVERRIDE_MARKER
+            };
+            write!(output, " {}", marker).unwrap();
+
+            let path = match module {
+                Some(module) => format!("{}.{}", module, key),
+                None => key.to_string(),
+            };
+            if let Some(source) = find_source(sources, &path) {
+                write!(output, " in {}", source.display()).unwrap();
+            }
+            if !is_valid {
+                write!(output, ", see `starship config check`").unwrap();
+            }
+        }
+        writeln!(output).unwrap();
+    }
+}
+
+/// Find the file which set the value at `path`. For a table, this is the file which set
+/// any of its values.
+fn find_source<'a>(sources: &'a BTreeMap<String, PathBuf>, path: &str) -> Option<&'a PathBuf> {
+    let nested_prefix = format!("{}.", path);
+    sources.get(path).or_else(|| {
+        sources
+            .range(nested_prefix.clone()..)
+            .take_while(|(key, _)| key.starts_with(&nested_prefix))
+            .map(|(_, source)| source)
+            .next()
+    })
+}
+
+/// Check a single option of the root config or of a module, as `starship config check` does
+fn is_valid_override(module: Option<&str>, key: &str, value: &Value, palette: &Palette) -> bool {
+    let mut config = Table::new();
+    config.insert(key.to_string(), value.clone());
+    // A custom module such as `custom.deploy` is nested in the `custom` table
+    for name in module.into_iter().flat_map(|module| module.rsplit('.')) {
+        let mut parent = Table::new();
+        parent.insert(name.to_string(), Value::Table(config));
+        config = parent;
+    }
+    config_check::check_config(&Value::Table(config), palette).is_empty()
+}
+
+/// Convert a config from its JSON form. TOML has no null, so unset options are left out.
+fn json_to_toml(value: &JsonValue) -> Option<Value> {
+    match value {
+        JsonValue::Null => None,
+        JsonValue::Bool(value) => Some(Value::Boolean(*value)),
+        JsonValue::Number(number) => number
+            .as_i64()
+            .map(Value::Integer)
+            .or_else(|| number.as_f64().map(Value::Float)),
+        JsonValue::String(value) => Some(Value::String(value.clone())),
+        JsonValue::Array(values) => Some(Value::Array(
+            values.iter().filter_map(json_to_toml).collect(),
+        )),
+        JsonValue::Object(values) => Some(Value::Table(
+            values
+                .iter()
+                .filter_map(|(key, value)| Some((key.clone(), json_to_toml(value)?)))
+                .collect(),
+        )),
+    }
+}
+
+/// Format a value on a single line, with tables written inline
+fn format_value(value: &Value) -> String {
+    match value {
+        Value::Table(table) => {
+            if table.is_empty() {
+                return String::from("{}");
+            }
+            let entries = table
+                .iter()
+                .map(|(key, value)| format!("{} = {}", format_key(key), format_value(value)))
+                .collect::<Vec<String>>();
+            format!("{{ {} }}", entries.join(", "))
+        }
+        Value::Array(values) => {
+            let values = values.iter().map(format_value).collect::<Vec<String>>();
+            format!("[{}]", values.join(", "))
+        }
+        value => value.to_string(),
+    }
+}
+
+/// Quote a key unless it is a valid bare key
+fn format_key(key: &str) -> String {
+    let is_bare = !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if is_bare {
+        key.to_string()
+    } else {
+        Value::String(key.to_string()).to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(config: Value) -> StarshipConfig {
+        StarshipConfig {
+            config: Some(config),
+            ..StarshipConfig::default()
+        }
+    }
+
+    #[test]
+    fn defaults_of_a_module() {
+        let output = render_config(&StarshipConfig::default(), &["directory"]).unwrap();
+
+        assert_eq!(
+            output,
+            "\n[directory]\n\
+             disabled = false\n\
+             fish_style_pwd_dir_length = 0\n\
+             prefix = \"in \"\n\
+             style = \"bold fg:cyan\"\n\
+             truncate_to_repo = true\n\
+             truncation_length = 3\n\
+             use_logical_path = true\n"
+        );
+    }
+
+    #[test]
+    fn overrides_are_marked() {
+        let config = config(toml::toml! {
+            [directory]
+            truncation_length = 1
+            format = "$path"
+
+            [git_status]
+            ahead = { value = "up", style = "green" }
+        });
+        let output = render_config(&config, &["directory", "git_status"]).unwrap();
+
+        assert!(output.starts_with("# Options set in a configuration file are marked"));
+        assert!(output.contains("\ntruncation_length = 1 # overridden\n"));
+        assert!(output.contains("\nformat = \"$path\" # overridden\n"));
+        assert!(output.contains("\ntruncate_to_repo = true\n"));
+        assert!(
+            output.contains("\nahead = { style = \"fg:green\", value = \"up\" } # overridden\n")
+        );
+        assert!(output.contains("\nstashed_count = { enabled = false }\n"));
+    }
+
+    #[test]
+    fn sources_are_shown() {
+        let mut config = config(toml::toml! {
+            [directory]
+            truncation_length = 1
+            prefix = 1
+
+            [git_status]
+            ahead = { value = "up", style = "green" }
+        });
+        let team = PathBuf::from("/team.toml");
+        let local = PathBuf::from("/local.toml");
+        config
+            .sources
+            .insert("directory.truncation_length".into(), team.clone());
+        config.sources.insert("directory.prefix".into(), team);
+        config
+            .sources
+            .insert("git_status.ahead.style".into(), local.clone());
+        config
+            .sources
+            .insert("git_status.ahead.value".into(), local);
+        let output = render_config(&config, &["directory", "git_status"]).unwrap();
+
+        assert!(output.contains("\ntruncation_length = 1 # overridden in /team.toml\n"));
+        assert!(output.contains(
+            "\nprefix = \"in \" # invalid override in /team.toml, see `starship config check`\n"
+        ));
+        assert!(output.contains("} # overridden in /local.toml\n"));
+    }
+
+    #[test]
+    fn invalid_overrides_are_marked() {
+        let config = config(toml::toml! {
+            format = "[$all"
+
+            [directory]
+            truncation_length = "3"
+        });
+        let output = render_config(&config, &[]).unwrap();
+
+        assert!(output
+            .contains("\nformat = \"[$all\" # invalid override, see `starship config check`\n"));
+        assert!(output
+            .contains("\ntruncation_length = 3 # invalid override, see `starship config check`\n"));
+    }
+
+    #[test]
+    fn root_and_every_module() {
+        let output = render_config(&config(toml::toml! { add_newline = false }), &[]).unwrap();
+
+        assert!(output.contains("\nadd_newline = false # overridden\n"));
+        for module in ALL_MODULES {
+            assert!(output.contains(&format!("\n[{}]\n", module)));
+        }
+        assert!(output.parse::<Value>().is_ok());
+    }
+
+    #[test]
+    fn custom_modules() {
+        let config = config(toml::toml! {
+            [custom.deploy]
+            command = "echo prod"
+            when = "test -f deploy.yaml"
+            shell = 1
+        });
+        let output = render_config(&config, &[]).unwrap();
+
+        assert!(output.contains("\n[custom.deploy]\n"));
+        assert!(output.contains("\ncommand = \"echo prod\" # overridden\n"));
+        assert!(output.contains("\nwhen = \"test -f deploy.yaml\" # overridden\n"));
+        assert!(output.contains("\nshell = [] # invalid override"));
+        assert_eq!(
+            render_config(&config, &["custom.vpn"]),
+            Err(String::from("custom.vpn"))
+        );
+    }
+
+    #[test]
+    fn unknown_module() {
+        assert_eq!(
+            render_config(&StarshipConfig::default(), &["directroy"]),
+            Err(String::from("directroy"))
+        );
+    }
+
+    #[test]
+    fn keys_are_quoted_when_needed() {
+        assert_eq!(format_key("us-east-1"), "us-east-1");
+        assert_eq!(format_key("a.b"), "\"a.b\"");
+        assert_eq!(format_key(""), "\"\"");
+    }
+}
@@ -0,0 +1,164 @@
+use ansi_term::Color;
+use serde_json::{json, Map, Value as JsonValue};
+
+use crate::config::{ModuleConfig, RootModuleConfig};
+use crate::config_check::COMMON_MODULE_KEYS;
+use crate::configs::conditions::ConditionsConfig;
+use crate::configs::custom::CustomConfig;
+use crate::configs::{self, StarshipRootConfig};
+use crate::module::ALL_MODULES;
+
+/// Build a JSON schema of the configuration file, for editors to offer completion and
+/// validation. It covers the root options and every module, with their default values.
+pub fn schema() -> JsonValue {
+    let root_schema = with_defaults(
+        StarshipRootConfig::schema(),
+        &StarshipRootConfig::new().to_json(),
+    );
+    let mut properties = match root_schema.get("properties") {
+        Some(JsonValue::Object(properties)) => properties.clone(),
+        _ => Map::new(),
+    };
+
+    for module in ALL_MODULES {
+        if let Some(mut module_schema) = configs::module_schema(module) {
+            add_common_keys(&mut module_schema);
+            properties.insert((*module).to_owned(), module_schema);
+        }
+    }
+
+    // Custom modules are named by the user, so any key of the `custom` table is allowed
+    let mut custom_schema = with_defaults(CustomConfig::schema(), &CustomConfig::new().to_json());
+    add_common_keys(&mut custom_schema);
+    // The `when` option of a custom module can also be a command
+    custom_schema["properties"]["when"] =
+        json!({ "anyOf": [{ "type": "string" }, ConditionsConfig::schema()] });
+    properties.insert(
+        "custom".to_owned(),
+        json!({ "type": "object", "additionalProperties": custom_schema }),
+    );
+
+    json!({
+        "$schema": "http://json-schema.org/draft-07/schema#",
+        "title": "Starship configuration",
+        "type": "object",
+        "properties": properties,
+        "additionalProperties": false,
+    })
+}
+
+/// Add the options which are read for every module to the schema of a module
+fn add_common_keys(module_schema: &mut JsonValue) {
+    let properties = match module_schema.get_mut("properties") {
+        Some(JsonValue::Object(properties)) => properties,
+        _ => return,
+    };
+
+    for key in COMMON_MODULE_KEYS {
+        let key_schema = match *key {
+            "format" => <&str>::schema(),
+            "disabled" => with_defaults(bool::schema(), &json!(false)),
+            "command_timeout" => u64::schema(),
+            "when" | "only_if" => ConditionsConfig::schema(),
+            "not_in" => <Vec<&str>>::schema(),
+            "background" => Color::schema(),
+            _ => continue,
+        };
+        properties.entry(*key).or_insert(key_schema);
+    }
+}
+
+/// Set the `default` of each property in `schema` from the matching value in `defaults`,
+/// which is the JSON form of a config as given by `ModuleConfig::to_json`
+pub fn with_defaults(mut schema: JsonValue, defaults: &JsonValue) -> JsonValue {
+    if defaults.is_null() {
+        return schema;
+    }
+
+    if let Some(JsonValue::Object(properties)) = schema.get_mut("properties") {
+        // A nested config, such as the counts of `git_status`, gets defaults for each option
+        for (key, property) in properties.iter_mut() {
+            if let Some(default) = defaults.get(key) {
+                *property = with_defaults(property.take(), default);
+            }
+        }
+    } else if let JsonValue::Object(schema) = &mut schema {
+        schema.insert("default".to_owned(), defaults.clone());
+    }
+    schema
+}
+
+/// Print the JSON schema of the configuration file
+pub fn print_schema() {
+    match serde_json::to_string_pretty(&schema()) {
+        Ok(schema) => println!("{}", schema),
+        Err(error) => eprintln!("Unable to serialize the schema: {}", error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn schema_covers_root_and_modules() {
+        let schema = schema();
+        let properties = schema["properties"].as_object().unwrap();
+
+        assert_eq!(properties["add_newline"]["type"], "boolean");
+        assert_eq!(properties["add_newline"]["default"], true);
+        assert_eq!(properties["format"]["default"], "$all");
+        for module in ALL_MODULES {
+            assert!(properties.contains_key(*module), "missing {}", module);
+            for key in COMMON_MODULE_KEYS {
+                assert!(properties[*module]["properties"].get(key).is_some());
+            }
+        }
+    }
+
+    #[test]
+    fn module_schema_has_types_and_defaults() {
+        let schema = schema();
+        let directory = &schema["properties"]["directory"];
+
+        assert_eq!(directory["additionalProperties"], false);
+        assert_eq!(
+            directory["properties"]["truncation_length"],
+            json!({ "type": "integer", "default": 3 })
+        );
+        assert_eq!(
+            directory["properties"]["style"],
+            json!({ "type": "string", "default": "bold fg:cyan" })
+        );
+        let git_status = &schema["properties"]["git_status"]["properties"];
+        assert_eq!(git_status["ahead"]["default"], "⇡");
+        assert_eq!(
+            git_status["stashed_count"]["properties"]["enabled"],
+            json!({ "type": "boolean", "default": false })
+        );
+    }
+
+    #[test]
+    fn custom_modules_accept_any_name() {
+        let schema = schema();
+        let custom = &schema["properties"]["custom"]["additionalProperties"];
+
+        assert_eq!(custom["properties"]["command"]["type"], "string");
+        assert_eq!(custom["properties"]["when"]["anyOf"][0]["type"], "string");
+        assert_eq!(custom["properties"]["when"]["anyOf"][1]["type"], "object");
+        assert!(custom["properties"]["disabled"].is_object());
+    }
+
+    #[test]
+    fn defaults_skip_unset_options() {
+        let schema = with_defaults(
+            json!({ "type": "object", "properties": { "a": {}, "b": {} } }),
+            &json!({ "a": 1, "b": null }),
+        );
+
+        assert_eq!(
+            schema,
+            json!({ "type": "object", "properties": { "a": { "default": 1 }, "b": {} } })
+        );
+    }
+}
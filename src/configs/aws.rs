@@ -2,6 +2,7 @@ use crate::config::{ModuleConfig, RootModuleConfig, SegmentConfig};
 use std::collections::HashMap;
 
 use ansi_term::{Color, Style};
+use serde_json::json;
 use starship_module_config_derive::ModuleConfig;
 
 #[derive(Clone, PartialEq)]
@@ -45,4 +46,16 @@ impl<'a> ModuleConfig<'a> for AwsItems {
             _ => None,
         }
     }
+
+    fn schema() -> serde_json::Value {
+        json!({ "type": "string", "enum": ["all", "region", "profile"] })
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        match self {
+            AwsItems::All => json!("all"),
+            AwsItems::Region => json!("region"),
+            AwsItems::Profile => json!("profile"),
+        }
+    }
 }
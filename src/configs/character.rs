@@ -8,9 +8,17 @@ pub struct CharacterConfig<'a> {
     pub symbol: SegmentConfig<'a>,
     pub error_symbol: SegmentConfig<'a>,
     pub vicmd_symbol: SegmentConfig<'a>,
+    pub replace_symbol: SegmentConfig<'a>,
+    pub replace_one_symbol: SegmentConfig<'a>,
+    pub visual_symbol: SegmentConfig<'a>,
+    pub operator_pending_symbol: SegmentConfig<'a>,
     pub use_symbol_for_status: bool,
     pub style_success: Style,
     pub style_failure: Style,
+    pub style_replace: Style,
+    pub style_replace_one: Style,
+    pub style_visual: Style,
+    pub style_operator_pending: Style,
     pub disabled: bool,
 }
 
@@ -20,9 +28,17 @@ impl<'a> RootModuleConfig<'a> for CharacterConfig<'a> {
             symbol: SegmentConfig::new("❯"),
             error_symbol: SegmentConfig::new("✖"),
             vicmd_symbol: SegmentConfig::new("❮"),
+            replace_symbol: SegmentConfig::new("❮"),
+            replace_one_symbol: SegmentConfig::new("❮"),
+            visual_symbol: SegmentConfig::new("❮"),
+            operator_pending_symbol: SegmentConfig::new("❮"),
             use_symbol_for_status: false,
             style_success: Color::Green.bold(),
             style_failure: Color::Red.bold(),
+            style_replace: Color::Purple.bold(),
+            style_replace_one: Color::Purple.bold(),
+            style_visual: Color::Yellow.bold(),
+            style_operator_pending: Color::Cyan.bold(),
             disabled: false,
         }
     }
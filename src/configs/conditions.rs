@@ -0,0 +1,25 @@
+use crate::config::{ModuleConfig, RootModuleConfig};
+
+use starship_module_config_derive::ModuleConfig;
+use std::collections::HashMap;
+
+/// The conditions of the `when` option, which every module accepts. A module is only
+/// shown if all of the conditions which are set hold.
+#[derive(Clone, ModuleConfig)]
+pub struct ConditionsConfig<'a> {
+    pub files: Vec<&'a str>,
+    pub env: HashMap<String, &'a str>,
+    pub directories: Vec<&'a str>,
+    pub ssh: Option<bool>,
+}
+
+impl<'a> RootModuleConfig<'a> for ConditionsConfig<'a> {
+    fn new() -> Self {
+        ConditionsConfig {
+            files: Vec::new(),
+            env: HashMap::new(),
+            directories: Vec::new(),
+            ssh: None,
+        }
+    }
+}
@@ -0,0 +1,39 @@
+use crate::config::{ModuleConfig, RootModuleConfig, SegmentConfig};
+
+use ansi_term::{Color, Style};
+use starship_module_config_derive::ModuleConfig;
+
+#[derive(Clone, ModuleConfig)]
+pub struct CustomConfig<'a> {
+    pub command: &'a str,
+    pub when: Option<&'a str>,
+    pub shell: Vec<&'a str>,
+    pub files: Vec<&'a str>,
+    pub extensions: Vec<&'a str>,
+    pub directories: Vec<&'a str>,
+    pub symbol: Option<SegmentConfig<'a>>,
+    pub style: Style,
+    pub prefix: &'a str,
+    pub suffix: &'a str,
+    pub description: &'a str,
+    pub disabled: bool,
+}
+
+impl<'a> RootModuleConfig<'a> for CustomConfig<'a> {
+    fn new() -> Self {
+        CustomConfig {
+            command: "",
+            when: None,
+            shell: Vec::new(),
+            files: Vec::new(),
+            extensions: Vec::new(),
+            directories: Vec::new(),
+            symbol: None,
+            style: Color::Green.bold(),
+            prefix: "via ",
+            suffix: " ",
+            description: "<custom command>",
+            disabled: false,
+        }
+    }
+}
@@ -0,0 +1,21 @@
+use crate::config::{ModuleConfig, RootModuleConfig};
+
+use ansi_term::{Color, Style};
+use starship_module_config_derive::ModuleConfig;
+
+#[derive(Clone, ModuleConfig)]
+pub struct FillConfig<'a> {
+    pub symbol: &'a str,
+    pub style: Style,
+    pub disabled: bool,
+}
+
+impl<'a> RootModuleConfig<'a> for FillConfig<'a> {
+    fn new() -> Self {
+        FillConfig {
+            symbol: ".",
+            style: Color::Black.bold(),
+            disabled: false,
+        }
+    }
+}
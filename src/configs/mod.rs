@@ -3,9 +3,12 @@ pub mod battery;
 pub mod character;
 pub mod cmd_duration;
 pub mod conda;
+pub mod conditions;
+pub mod custom;
 pub mod directory;
 pub mod dotnet;
 pub mod env_var;
+pub mod fill;
 pub mod git_branch;
 pub mod git_commit;
 pub mod git_state;
@@ -25,8 +28,109 @@ pub mod python;
 pub mod ruby;
 pub mod rust;
 mod starship_root;
+pub mod status;
 pub mod terraform;
 pub mod time;
 pub mod username;
 
 pub use starship_root::*;
+
+use crate::config::{ConfigDiagnostic, ModuleConfig, Palette, RootModuleConfig};
+use crate::config_schema;
+use serde_json::json;
+
+/// Generate the functions which find the config type of a module from its name, so that
+/// each module is only listed once. Custom modules and `line_break` are handled separately.
+macro_rules! module_configs {
+    ($($name:literal => $config:ty,)*) => {
+        /// Check the config table of a module against `palette`, or return `None` if there is
+        /// no module named `name`
+        pub fn validate_module(
+            name: &str,
+            config: &toml::Value,
+            palette: &Palette,
+        ) -> Option<Vec<ConfigDiagnostic>> {
+            let diagnostics = match name {
+                $($name => <$config>::validate(config, palette),)*
+                name if name.starts_with("custom.") => {
+                    custom::CustomConfig::validate(config, palette)
+                }
+                // line_break has no options besides the common ones
+                "line_break" => Vec::new(),
+                _ => return None,
+            };
+            Some(diagnostics)
+        }
+
+        /// Describe the config table of a module as a JSON schema, including its default
+        /// values, or return `None` if there is no module named `name`
+        pub fn module_schema(name: &str) -> Option<serde_json::Value> {
+            let schema = match name {
+                $($name => schema_with_defaults::<$config>(),)*
+                // line_break has no options besides the common ones
+                "line_break" => {
+                    json!({ "type": "object", "properties": {}, "additionalProperties": false })
+                }
+                _ => return None,
+            };
+            Some(schema)
+        }
+
+        /// The effective config of a module as JSON, with the defaults of any options missing
+        /// from `config`, or `None` if there is no module named `name`
+        pub fn module_config(
+            name: &str,
+            config: Option<&toml::Value>,
+        ) -> Option<serde_json::Value> {
+            let resolved = match name {
+                $($name => resolve::<$config>(config),)*
+                name if name.starts_with("custom.") => resolve::<custom::CustomConfig>(config),
+                "line_break" => json!({}),
+                _ => return None,
+            };
+            Some(resolved)
+        }
+    };
+}
+
+module_configs! {
+    "aws" => aws::AwsConfig,
+    "battery" => battery::BatteryConfig,
+    "character" => character::CharacterConfig,
+    "cmd_duration" => cmd_duration::CmdDurationConfig,
+    "conda" => conda::CondaConfig,
+    "directory" => directory::DirectoryConfig,
+    "dotnet" => dotnet::DotnetConfig,
+    "env_var" => env_var::EnvVarConfig,
+    "fill" => fill::FillConfig,
+    "git_branch" => git_branch::GitBranchConfig,
+    "git_commit" => git_commit::GitCommitConfig,
+    "git_state" => git_state::GitStateConfig,
+    "git_status" => git_status::GitStatusConfig,
+    "golang" => go::GoConfig,
+    "hg_branch" => hg_branch::HgBranchConfig,
+    "hostname" => hostname::HostnameConfig,
+    "java" => java::JavaConfig,
+    "jobs" => jobs::JobsConfig,
+    "kubernetes" => kubernetes::KubernetesConfig,
+    "memory_usage" => memory_usage::MemoryConfig,
+    "nix_shell" => nix_shell::NixShellConfig,
+    "nodejs" => nodejs::NodejsConfig,
+    "package" => package::PackageConfig,
+    "php" => php::PhpConfig,
+    "python" => python::PythonConfig,
+    "ruby" => ruby::RubyConfig,
+    "rust" => rust::RustConfig,
+    "status" => status::StatusConfig,
+    "terraform" => terraform::TerraformConfig,
+    "time" => time::TimeConfig,
+    "username" => username::UsernameConfig,
+}
+
+fn resolve<'a, T: RootModuleConfig<'a>>(config: Option<&'a toml::Value>) -> serde_json::Value {
+    T::try_load(config).to_json()
+}
+
+fn schema_with_defaults<'a, T: RootModuleConfig<'a>>() -> serde_json::Value {
+    config_schema::with_defaults(T::schema(), &T::new().to_json())
+}
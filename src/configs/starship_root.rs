@@ -1,58 +1,113 @@
+use crate::color::ColorMode;
 use crate::config::{ModuleConfig, RootModuleConfig};
 
+use ansi_term::Color;
 use starship_module_config_derive::ModuleConfig;
+use std::collections::HashMap;
 
 #[derive(Clone, ModuleConfig)]
 pub struct StarshipRootConfig<'a> {
     pub add_newline: bool,
-    pub prompt_order: Vec<&'a str>,
+    pub format: &'a str,
+    pub right_format: &'a str,
+    pub transient_format: &'a str,
+    pub continuation_prompt: &'a str,
     pub scan_timeout: u64,
+    pub command_timeout: u64,
+    pub include: Vec<&'a str>,
+    pub trusted_directories: Vec<&'a str>,
+    pub palette: Option<&'a str>,
+    pub palettes: HashMap<String, HashMap<String, &'a str>>,
+    pub color_mode: ColorMode,
+    pub shell_integration: bool,
+    pub powerline: PowerlineConfig<'a>,
+    pub title: TitleConfig<'a>,
 }
 
+#[derive(Clone, ModuleConfig)]
+pub struct PowerlineConfig<'a> {
+    pub enabled: bool,
+    pub separator: &'a str,
+    pub right_separator: &'a str,
+    pub background: Option<Color>,
+}
+
+#[derive(Clone, ModuleConfig)]
+pub struct TitleConfig<'a> {
+    pub enabled: bool,
+    pub format: &'a str,
+    pub command_format: &'a str,
+}
+
+// List of default prompt order
+// NOTE: If this const value is changed then Default prompt order subheading inside
+// prompt heading of config docs needs to be updated according to changes made here.
+pub const PROMPT_ORDER: &[&str] = &[
+    "username",
+    "hostname",
+    "kubernetes",
+    "directory",
+    "git_branch",
+    "git_commit",
+    "git_state",
+    "git_status",
+    "hg_branch",
+    "package",
+    // ↓ Toolchain version modules ↓
+    // (Let's keep these sorted alphabetically)
+    "dotnet",
+    "golang",
+    "java",
+    "nodejs",
+    "php",
+    "python",
+    "ruby",
+    "rust",
+    "terraform",
+    // ↑ Toolchain version modules ↑
+    "nix_shell",
+    "conda",
+    "memory_usage",
+    "aws",
+    "env_var",
+    "custom",
+    "cmd_duration",
+    "line_break",
+    "jobs",
+    #[cfg(feature = "battery")]
+    "battery",
+    "time",
+    "status",
+    "character",
+];
+
 impl<'a> RootModuleConfig<'a> for StarshipRootConfig<'a> {
     fn new() -> Self {
         StarshipRootConfig {
             add_newline: true,
-            // List of default prompt order
-            // NOTE: If this const value is changed then Default prompt order subheading inside
-            // prompt heading of config docs needs to be updated according to changes made here.
-            prompt_order: vec![
-                "username",
-                "hostname",
-                "kubernetes",
-                "directory",
-                "git_branch",
-                "git_commit",
-                "git_state",
-                "git_status",
-                "hg_branch",
-                "package",
-                // ↓ Toolchain version modules ↓
-                // (Let's keep these sorted alphabetically)
-                "dotnet",
-                "golang",
-                "java",
-                "nodejs",
-                "php",
-                "python",
-                "ruby",
-                "rust",
-                "terraform",
-                // ↑ Toolchain version modules ↑
-                "nix_shell",
-                "conda",
-                "memory_usage",
-                "aws",
-                "env_var",
-                "cmd_duration",
-                "line_break",
-                "jobs",
-                #[cfg(feature = "battery")]
-                "battery",
-                "time",
-                "character",
-            ],
+            format: "$all",
+            right_format: "",
+            transient_format: "",
+            continuation_prompt: "[∙](bright-black) ",
             scan_timeout: 30,
+            command_timeout: 500,
+            include: vec![],
+            trusted_directories: vec![],
+            palette: None,
+            palettes: HashMap::new(),
+            color_mode: ColorMode::Auto,
+            shell_integration: false,
+            powerline: PowerlineConfig {
+                enabled: false,
+                separator: "\u{e0b0}",
+                right_separator: "\u{e0b2}",
+                background: None,
+            },
+            title: TitleConfig {
+                enabled: false,
+                format: "$directory",
+                command_format: "$command",
+            },
         }
     }
 }
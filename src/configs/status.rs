@@ -0,0 +1,25 @@
+use crate::config::{ModuleConfig, RootModuleConfig, SegmentConfig};
+
+use ansi_term::{Color, Style};
+use starship_module_config_derive::ModuleConfig;
+
+#[derive(Clone, ModuleConfig)]
+pub struct StatusConfig<'a> {
+    pub symbol: SegmentConfig<'a>,
+    pub pipestatus_separator: &'a str,
+    pub show_meaning: bool,
+    pub style: Style,
+    pub disabled: bool,
+}
+
+impl<'a> RootModuleConfig<'a> for StatusConfig<'a> {
+    fn new() -> Self {
+        StatusConfig {
+            symbol: SegmentConfig::new("✖ "),
+            pipestatus_separator: "|",
+            show_meaning: true,
+            style: Color::Red.bold(),
+            disabled: true,
+        }
+    }
+}
@@ -6,17 +6,23 @@ use starship_module_config_derive::ModuleConfig;
 #[derive(Clone, ModuleConfig)]
 pub struct TimeConfig<'a> {
     pub use_12hr: bool,
-    pub format: Option<&'a str>,
+    pub time_format: Option<&'a str>,
     pub style: Style,
     pub disabled: bool,
     pub utc_time_offset: &'a str,
 }
 
+/// Whether the `format` of the time module is a strftime format, as `format` was before it
+/// became a format string. It is then used as the `time_format`.
+pub fn is_legacy_format(format: &str) -> bool {
+    format.contains('%') && !format.contains('$')
+}
+
 impl<'a> RootModuleConfig<'a> for TimeConfig<'a> {
     fn new() -> Self {
         TimeConfig {
             use_12hr: false,
-            format: None,
+            time_format: None,
             style: Color::Yellow.bold(),
             disabled: true,
             utc_time_offset: "local",
@@ -1,5 +1,8 @@
-use crate::config::StarshipConfig;
+use crate::color;
+use crate::config::{ModuleConfig, RootModuleConfig, StarshipConfig};
+use crate::configs::conditions::ConditionsConfig;
 use crate::module::Module;
+use crate::utils;
 
 use crate::modules;
 use clap::ArgMatches;
@@ -31,6 +34,9 @@ pub struct Context<'a> {
 
     /// Private field to store Git information for modules who need it
     repo: OnceCell<Repo>,
+
+    /// The names of the custom modules defined in the configuration, such as `custom.deploy`
+    pub custom_modules: Vec<String>,
 }
 
 impl<'a> Context<'a> {
@@ -71,13 +77,31 @@ impl<'a> Context<'a> {
         // TODO: Currently gets the physical directory. Get the logical directory.
         let current_dir = Context::expand_tilde(dir.into());
 
-        Context {
+        let mut context = Context {
             config,
             properties,
             current_dir,
             dir_files: OnceCell::new(),
             repo: OnceCell::new(),
+            custom_modules: Vec::new(),
+        };
+        context.merge_local_config();
+        context.custom_modules = context.config.custom_module_names();
+        color::set_color_mode(context.config.get_root_config().color_mode);
+        context
+    }
+
+    /// Merge the directory-local config files of trusted directories into the config
+    fn merge_local_config(&mut self) {
+        // Looking for the repository has a cost, so it is skipped if no directory is trusted
+        if self.config.trusted_directories().is_empty() {
+            return;
         }
+
+        let repo_root = self.get_repo().ok().and_then(|repo| repo.root.clone());
+        let current_dir = self.current_dir.clone();
+        self.config
+            .merge_local_configs(&current_dir, repo_root.as_deref());
     }
 
     /// Convert a `~` in a path to the home directory
@@ -107,6 +131,90 @@ impl<'a> Context<'a> {
         disabled == Some(true)
     }
 
+    /// Check if the module is to be shown: it isn't disabled, the conditions of its
+    /// `when` and `only_if` options hold, and the current directory isn't in one of its
+    /// `not_in` directories.
+    pub fn is_module_shown(&self, name: &str) -> bool {
+        if self.is_module_disabled_in_config(name) {
+            return false;
+        }
+        let config = match self.config.get_module_config(name) {
+            Some(config) => config,
+            None => return true,
+        };
+
+        let not_in = config
+            .get("not_in")
+            .and_then(<Vec<&str>>::from_config)
+            .unwrap_or_default();
+        let excluded_dirs = not_in
+            .into_iter()
+            .map(utils::expand_home)
+            .collect::<Vec<PathBuf>>();
+        if utils::is_in_directories(&self.current_dir, &excluded_dirs) {
+            log::debug!("Hiding {}, as the current directory is in `not_in`", name);
+            return false;
+        }
+
+        // `only_if` is another name for `when`, which custom modules also accept as a
+        // command of their own
+        for key in &["when", "only_if"] {
+            let conditions = ConditionsConfig::try_load(config.get(*key));
+            if !self.conditions_hold(&conditions) {
+                log::debug!("Hiding {}, as the conditions of `{}` don't hold", name, key);
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Check the conditions of a `when` option, leaving the file lookup to the end as it
+    /// is the most costly
+    fn conditions_hold(&self, conditions: &ConditionsConfig) -> bool {
+        if let Some(ssh) = conditions.ssh {
+            if is_ssh_session() != ssh {
+                return false;
+            }
+        }
+
+        let env_matches = conditions.env.iter().all(|(name, pattern)| {
+            env::var(name)
+                .map(|value| utils::matches_wildcard(pattern, &value))
+                .unwrap_or(false)
+        });
+        if !env_matches {
+            return false;
+        }
+
+        if !conditions.directories.is_empty() {
+            let dirs = conditions
+                .directories
+                .iter()
+                .map(|dir| utils::expand_home(dir))
+                .collect::<Vec<PathBuf>>();
+            if !utils::is_in_directories(&self.current_dir, &dirs) {
+                return false;
+            }
+        }
+
+        if conditions.files.is_empty() {
+            return true;
+        }
+        // The lookup stops at the root of the repository or at the home directory, rather
+        // than listing every parent up to `/` on each prompt
+        let repo_root = self.get_repo().ok().and_then(|repo| repo.root.as_deref());
+        let home_dir = dirs::home_dir();
+        for dir in self.current_dir.ancestors() {
+            if has_matching_file(dir, &conditions.files) {
+                return true;
+            }
+            if Some(dir) == repo_root || Some(dir) == home_dir.as_deref() {
+                break;
+            }
+        }
+        false
+    }
+
     // returns a new ScanDir struct with reference to current dir_files of context
     // see ScanDir for methods
     pub fn try_begin_scan(&'a self) -> Option<ScanDir<'a>> {
@@ -245,6 +353,40 @@ pub fn has_extension<'a>(dir_entry: &PathBuf, extensions: &'a [&'a str]) -> bool
     false
 }
 
+/// Whether the shell runs over SSH. Some sshd setups, as well as `sudo -E`, only leave
+/// `SSH_CLIENT` or `SSH_TTY` set rather than `SSH_CONNECTION`.
+fn is_ssh_session() -> bool {
+    ["SSH_CONNECTION", "SSH_CLIENT", "SSH_TTY"]
+        .iter()
+        .any(|name| env::var_os(name).is_some())
+}
+
+/// Check whether a file in `dir` matches one of the patterns. A pattern without
+/// wildcards is looked up directly, rather than by listing the directory.
+fn has_matching_file(dir: &Path, patterns: &[&str]) -> bool {
+    let (wildcards, names): (Vec<&str>, Vec<&str>) = patterns
+        .iter()
+        .partition(|pattern| pattern.contains(&['*', '?'][..]));
+    if names.iter().any(|name| dir.join(name).exists()) {
+        return true;
+    }
+    if wildcards.is_empty() {
+        return false;
+    }
+
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return false,
+    };
+    entries.filter_map(Result::ok).any(|entry| {
+        let file_name = entry.file_name();
+        let file_name = file_name.to_string_lossy();
+        wildcards
+            .iter()
+            .any(|pattern| utils::matches_wildcard(pattern, &file_name))
+    })
+}
+
 fn get_current_branch(repository: &Repository) -> Option<String> {
     let head = repository.head().ok()?;
     let shorthand = head.shorthand();
@@ -310,6 +452,20 @@ mod tests {
         assert_eq!(failing_dir_criteria.is_match(), false);
     }
 
+    #[test]
+    fn test_has_matching_file() -> std::io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        fs::write(dir.path().join("main.tf"), "")?;
+        fs::create_dir(dir.path().join("src"))?;
+
+        assert!(has_matching_file(dir.path(), &["main.tf"]));
+        assert!(has_matching_file(dir.path(), &["Cargo.toml", "*.tf"]));
+        assert!(has_matching_file(dir.path(), &["sr?"]));
+        assert!(!has_matching_file(dir.path(), &["*.tfvars", "Cargo.toml"]));
+        assert!(!has_matching_file(dir.path(), &[]));
+        dir.close()
+    }
+
     #[test]
     fn test_criteria_scan_passes() {
         let passing_criteria = ScanDir {
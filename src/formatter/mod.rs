@@ -0,0 +1,7 @@
+mod model;
+mod parser;
+mod string_formatter;
+
+pub use model::{FormatElement, StyleElement};
+pub use parser::parse;
+pub use string_formatter::StringFormatter;
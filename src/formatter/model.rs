@@ -0,0 +1,47 @@
+/// An element of a parsed format string
+#[derive(Clone, Debug, PartialEq)]
+pub enum FormatElement<'a> {
+    /// Literal text, with any escape sequences already resolved
+    Text(String),
+
+    /// A variable, written as `$name` in the format string
+    Variable(&'a str),
+
+    /// Text which is styled as a whole, written as `[format](style)`
+    TextGroup(TextGroup<'a>),
+
+    /// A group which is only shown if any of the variables within it has a value,
+    /// written as `(format)`
+    Conditional(Vec<FormatElement<'a>>),
+}
+
+/// The contents and style of a text group
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextGroup<'a> {
+    pub format: Vec<FormatElement<'a>>,
+    pub style: StyleElement<'a>,
+}
+
+/// The style of a text group
+#[derive(Clone, Debug, PartialEq)]
+pub enum StyleElement<'a> {
+    /// A style string, such as `bold red`
+    Text(&'a str),
+
+    /// A variable holding a style, such as `$style`
+    Variable(&'a str),
+}
+
+impl<'a> FormatElement<'a> {
+    /// Collect the names of all variables used by this element, including nested ones
+    pub fn variables(&self) -> Vec<&'a str> {
+        match self {
+            FormatElement::Text(_) => Vec::new(),
+            FormatElement::Variable(name) => vec![name],
+            FormatElement::TextGroup(group) => {
+                group.format.iter().flat_map(Self::variables).collect()
+            }
+            FormatElement::Conditional(format) => format.iter().flat_map(Self::variables).collect(),
+        }
+    }
+}
@@ -0,0 +1,208 @@
+use nom::{
+    branch::alt,
+    bytes::complete::{is_not, tag, take_while1},
+    character::complete::{char, one_of},
+    combinator::{all_consuming, map, opt, recognize},
+    multi::{many0, many1},
+    sequence::{delimited, pair, preceded},
+    IResult,
+};
+
+use super::model::{FormatElement, StyleElement, TextGroup};
+
+/// Characters which must be escaped with a backslash to be used as literal text
+const ESCAPABLE_CHARS: &str = "$\\[]()";
+
+fn is_variable_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+/// A variable name, or the name of a custom module such as `custom.deploy`
+fn variable_name(input: &str) -> IResult<&str, &str> {
+    let custom_module = recognize(pair(tag("custom."), take_while1(is_variable_char)));
+    preceded(
+        char('$'),
+        alt((custom_module, take_while1(is_variable_char))),
+    )(input)
+}
+
+fn variable(input: &str) -> IResult<&str, FormatElement<'_>> {
+    map(variable_name, FormatElement::Variable)(input)
+}
+
+fn text(input: &str) -> IResult<&str, FormatElement<'_>> {
+    let plain = map(is_not(ESCAPABLE_CHARS), String::from);
+    let escaped = map(preceded(char('\\'), one_of(ESCAPABLE_CHARS)), String::from);
+
+    map(many1(alt((plain, escaped))), |chunks| {
+        FormatElement::Text(chunks.concat())
+    })(input)
+}
+
+fn style(input: &str) -> IResult<&str, StyleElement<'_>> {
+    map(
+        delimited(char('('), opt(is_not(")")), char(')')),
+        |style: Option<&str>| {
+            // A style consisting only of a variable refers to that variable's style
+            let style = style.unwrap_or_default().trim();
+            match all_consuming(variable_name)(style) {
+                Ok((_, name)) => StyleElement::Variable(name),
+                Err(_) => StyleElement::Text(style),
+            }
+        },
+    )(input)
+}
+
+fn text_group(input: &str) -> IResult<&str, FormatElement<'_>> {
+    map(
+        pair(delimited(char('['), format_elements, char(']')), style),
+        |(format, style)| FormatElement::TextGroup(TextGroup { format, style }),
+    )(input)
+}
+
+fn conditional(input: &str) -> IResult<&str, FormatElement<'_>> {
+    map(
+        delimited(char('('), format_elements, char(')')),
+        FormatElement::Conditional,
+    )(input)
+}
+
+fn format_elements(input: &str) -> IResult<&str, Vec<FormatElement<'_>>> {
+    many0(alt((text_group, conditional, variable, text)))(input)
+}
+
+/// Parse a format string into a list of elements.
+///
+/// Returns an error message describing where parsing stopped if the format string is
+/// malformed, e.g. it contains a lone `$` which isn't followed by a variable name or an
+/// unbalanced bracket.
+pub fn parse(format: &str) -> Result<Vec<FormatElement<'_>>, String> {
+    match all_consuming(format_elements)(format) {
+        Ok((_, elements)) => Ok(elements),
+        Err(nom::Err::Error((rest, _))) | Err(nom::Err::Failure((rest, _))) => Err(format!(
+            "unexpected input at position {}: {:?}",
+            format.len() - rest.len(),
+            rest
+        )),
+        Err(nom::Err::Incomplete(_)) => Err(String::from("unexpected end of format string")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(value: &str) -> FormatElement<'_> {
+        FormatElement::Text(value.to_owned())
+    }
+
+    #[test]
+    fn test_parse_text_only() {
+        let elements = parse("hello world").unwrap();
+        assert_eq!(elements, vec![text("hello world")]);
+    }
+
+    #[test]
+    fn test_parse_variables_and_text() {
+        let elements = parse("$username@$hostname $directory\n$character").unwrap();
+        assert_eq!(
+            elements,
+            vec![
+                FormatElement::Variable("username"),
+                text("@"),
+                FormatElement::Variable("hostname"),
+                text(" "),
+                FormatElement::Variable("directory"),
+                text("\n"),
+                FormatElement::Variable("character"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_custom_modules() {
+        let elements = parse("$custom.deploy $custom. $custom").unwrap();
+        assert_eq!(
+            elements,
+            vec![
+                FormatElement::Variable("custom.deploy"),
+                text(" "),
+                FormatElement::Variable("custom"),
+                text(". "),
+                FormatElement::Variable("custom"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_escaped_chars() {
+        let elements = parse(r"\$HOME \\ \[\(\)\] $all").unwrap();
+        assert_eq!(
+            elements,
+            vec![text(r"$HOME \ [()] "), FormatElement::Variable("all")]
+        );
+    }
+
+    #[test]
+    fn test_parse_text_group() {
+        let elements = parse("on [$symbol$branch](bold purple) ").unwrap();
+        assert_eq!(
+            elements,
+            vec![
+                text("on "),
+                FormatElement::TextGroup(TextGroup {
+                    format: vec![
+                        FormatElement::Variable("symbol"),
+                        FormatElement::Variable("branch"),
+                    ],
+                    style: StyleElement::Text("bold purple"),
+                }),
+                text(" "),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_style_variable() {
+        let elements = parse("[x]($style)[y]()").unwrap();
+        assert_eq!(
+            elements,
+            vec![
+                FormatElement::TextGroup(TextGroup {
+                    format: vec![text("x")],
+                    style: StyleElement::Variable("style"),
+                }),
+                FormatElement::TextGroup(TextGroup {
+                    format: vec![text("y")],
+                    style: StyleElement::Text(""),
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_nested_conditional() {
+        let elements = parse("([⇡$ahead_count]($style) )").unwrap();
+        assert_eq!(
+            elements,
+            vec![FormatElement::Conditional(vec![
+                FormatElement::TextGroup(TextGroup {
+                    format: vec![text("⇡"), FormatElement::Variable("ahead_count")],
+                    style: StyleElement::Variable("style"),
+                }),
+                text(" "),
+            ])]
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(parse("$").is_err());
+        assert!(parse("text $ more").is_err());
+        assert!(parse(r"\n").is_err());
+        assert!(parse("[unclosed").is_err());
+        assert!(parse("[no style]").is_err());
+        assert!(parse("(unclosed").is_err());
+        assert!(parse("closed)").is_err());
+    }
+}
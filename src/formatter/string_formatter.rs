@@ -0,0 +1,327 @@
+use ansi_term::Style;
+use std::collections::BTreeMap;
+
+use super::model::{FormatElement, StyleElement};
+use super::parser::parse;
+use crate::config::parse_style_string;
+use crate::segment::Segment;
+
+type VariableMap = BTreeMap<String, Option<Vec<Segment>>>;
+type StyleVariableMap = BTreeMap<String, Option<Style>>;
+
+/// Renders a format string into segments, given values for the variables it contains.
+///
+/// Variables are filled in through the `map*` methods, each of which only maps the variables
+/// that don't have a value yet, so the most specific mappers should be applied first.
+pub struct StringFormatter<'a> {
+    format: Vec<FormatElement<'a>>,
+    variables: VariableMap,
+    style_variables: StyleVariableMap,
+}
+
+impl<'a> StringFormatter<'a> {
+    /// Parse a format string
+    pub fn new(format: &'a str) -> Result<Self, String> {
+        let format = parse(format)?;
+
+        let variables = format
+            .iter()
+            .flat_map(FormatElement::variables)
+            .map(|name| (name.to_owned(), None))
+            .collect::<VariableMap>();
+        let style_variables = format
+            .iter()
+            .flat_map(style_variables)
+            .map(|name| (name.to_owned(), None))
+            .collect::<StyleVariableMap>();
+
+        Ok(Self {
+            format,
+            variables,
+            style_variables,
+        })
+    }
+
+    /// Map variables to plain text, which will use the style of its surroundings
+    pub fn map<T, M>(mut self, mapper: M) -> Self
+    where
+        T: Into<String>,
+        M: Fn(&str) -> Option<T>,
+    {
+        for (name, value) in self
+            .variables
+            .iter_mut()
+            .filter(|(_, value)| value.is_none())
+        {
+            *value = mapper(name).map(|text| {
+                let mut segment = Segment::new(name);
+                segment.set_value(text);
+                vec![segment]
+            });
+        }
+        self
+    }
+
+    /// Map variables to segments, which keep their own style unless they are inside a
+    /// styled text group
+    pub fn map_variables_to_segments<M>(mut self, mapper: M) -> Self
+    where
+        M: Fn(&str) -> Option<Vec<Segment>>,
+    {
+        for (name, value) in self
+            .variables
+            .iter_mut()
+            .filter(|(_, value)| value.is_none())
+        {
+            *value = mapper(name);
+        }
+        self
+    }
+
+    /// Map the variables used as the style of text groups, e.g. `$style` in `[$symbol]($style)`
+    pub fn map_style<M>(mut self, mapper: M) -> Self
+    where
+        M: Fn(&str) -> Option<Style>,
+    {
+        for (name, value) in self
+            .style_variables
+            .iter_mut()
+            .filter(|(_, value)| value.is_none())
+        {
+            *value = mapper(name);
+        }
+        self
+    }
+
+    /// Render the format string into segments. Text and variables without a style of their
+    /// own are painted with `default_style`. Variables without a value are left out.
+    pub fn parse(self, default_style: Option<Style>) -> Vec<Segment> {
+        render_elements(&self.format, default_style, false, &self)
+    }
+}
+
+fn style_variables<'a>(element: &FormatElement<'a>) -> Vec<&'a str> {
+    match element {
+        FormatElement::TextGroup(group) => {
+            let mut names = group
+                .format
+                .iter()
+                .flat_map(style_variables)
+                .collect::<Vec<&str>>();
+            if let StyleElement::Variable(name) = group.style {
+                names.push(name);
+            }
+            names
+        }
+        FormatElement::Conditional(format) => format.iter().flat_map(style_variables).collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn render_elements(
+    elements: &[FormatElement],
+    style: Option<Style>,
+    in_text_group: bool,
+    formatter: &StringFormatter,
+) -> Vec<Segment> {
+    elements
+        .iter()
+        .flat_map(|element| render_element(element, style, in_text_group, formatter))
+        .collect()
+}
+
+fn render_element(
+    element: &FormatElement,
+    style: Option<Style>,
+    in_text_group: bool,
+    formatter: &StringFormatter,
+) -> Vec<Segment> {
+    match element {
+        FormatElement::Text(text) => {
+            let mut segment = Segment::new("_text");
+            segment.set_value(text.as_str());
+            if let Some(style) = style {
+                segment.set_style(style);
+            }
+            vec![segment]
+        }
+        FormatElement::Variable(name) => formatter
+            .variables
+            .get(*name)
+            .cloned()
+            .flatten()
+            .unwrap_or_else(|| {
+                log::trace!("Variable {:?} has no value", name);
+                Vec::new()
+            })
+            .into_iter()
+            .map(|mut segment| {
+                // Variables take the style of the text group they are in
+                if let Some(style) = style {
+                    if in_text_group || segment.get_style().is_none() {
+                        segment.set_style(style);
+                    }
+                }
+                segment
+            })
+            .collect(),
+        FormatElement::TextGroup(group) => {
+            let group_style = match group.style {
+                StyleElement::Text(style_string) => {
+                    parse_style_string(style_string).or_else(|| {
+                        log::warn!("Invalid style string in format: {:?}", style_string);
+                        style
+                    })
+                }
+                StyleElement::Variable(name) => formatter
+                    .style_variables
+                    .get(name)
+                    .cloned()
+                    .flatten()
+                    .or(style),
+            };
+            render_elements(&group.format, group_style, true, formatter)
+        }
+        FormatElement::Conditional(format) => {
+            let should_show = format
+                .iter()
+                .flat_map(FormatElement::variables)
+                .any(|name| match formatter.variables.get(name) {
+                    Some(Some(segments)) => segments.iter().any(|segment| !segment.is_empty()),
+                    _ => false,
+                });
+
+            if should_show {
+                render_elements(format, style, in_text_group, formatter)
+            } else {
+                Vec::new()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ansi_term::Color;
+
+    fn values(segments: &[Segment]) -> Vec<&str> {
+        segments.iter().map(Segment::get_value).collect()
+    }
+
+    fn styled_segment(name: &str, value: &str, style: Style) -> Segment {
+        let mut segment = Segment::new(name);
+        segment.set_value(value).set_style(style);
+        segment
+    }
+
+    #[test]
+    fn test_text_and_variables() {
+        let segments = StringFormatter::new("on $branch!")
+            .unwrap()
+            .map(|name| match name {
+                "branch" => Some("master"),
+                _ => None,
+            })
+            .parse(None);
+
+        assert_eq!(values(&segments), vec!["on ", "master", "!"]);
+        assert!(segments.iter().all(|segment| segment.get_style().is_none()));
+    }
+
+    #[test]
+    fn test_text_group_style() {
+        let segments = StringFormatter::new("[$symbol$version](bold red) ")
+            .unwrap()
+            .map(|name| match name {
+                "symbol" => Some("🦀 "),
+                "version" => Some("v1.41.0"),
+                _ => None,
+            })
+            .parse(None);
+
+        assert_eq!(values(&segments), vec!["🦀 ", "v1.41.0", " "]);
+        assert_eq!(segments[0].get_style(), Some(Color::Red.bold()));
+        assert_eq!(segments[1].get_style(), Some(Color::Red.bold()));
+        assert_eq!(segments[2].get_style(), None);
+    }
+
+    #[test]
+    fn test_style_variable() {
+        let segments = StringFormatter::new("[text]($style)")
+            .unwrap()
+            .map_style(|name| match name {
+                "style" => Some(Color::Blue.underline()),
+                _ => None,
+            })
+            .parse(None);
+
+        assert_eq!(segments[0].get_style(), Some(Color::Blue.underline()));
+    }
+
+    #[test]
+    fn test_segments_keep_their_style_outside_groups() {
+        let segments = StringFormatter::new("$symbol[$symbol](green)")
+            .unwrap()
+            .map_variables_to_segments(|name| match name {
+                "symbol" => Some(vec![styled_segment("symbol", "$", Color::Red.normal())]),
+                _ => None,
+            })
+            .parse(Some(Color::Yellow.normal()));
+
+        assert_eq!(segments[0].get_style(), Some(Color::Red.normal()));
+        assert_eq!(segments[1].get_style(), Some(Color::Green.normal()));
+    }
+
+    #[test]
+    fn test_conditional_group() {
+        let format = "$branch( ⇡$ahead_count)( ⇣$behind_count)";
+        let segments = StringFormatter::new(format)
+            .unwrap()
+            .map(|name| match name {
+                "branch" => Some("master"),
+                "ahead_count" => Some("2"),
+                "behind_count" => Some(""),
+                _ => None,
+            })
+            .parse(None);
+
+        assert_eq!(values(&segments), vec!["master", " ⇡", "2"]);
+    }
+
+    #[test]
+    fn test_nested_conditional_group() {
+        let format = "(\\(($ahead)($behind)\\))";
+        let formatter = |ahead: Option<&'static str>| {
+            StringFormatter::new(format)
+                .unwrap()
+                .map(|name| match name {
+                    "ahead" => ahead,
+                    _ => None,
+                })
+                .parse(None)
+        };
+
+        assert_eq!(values(&formatter(Some("⇡"))), vec!["(", "⇡", ")"]);
+        assert!(formatter(None).is_empty());
+    }
+
+    #[test]
+    fn test_map_only_fills_missing_variables() {
+        let segments = StringFormatter::new("$a$b")
+            .unwrap()
+            .map(|name| match name {
+                "a" => Some("first"),
+                _ => None,
+            })
+            .map(|_| Some("second"))
+            .parse(None);
+
+        assert_eq!(values(&segments), vec!["first", "second"]);
+    }
+
+    #[test]
+    fn test_invalid_format() {
+        assert!(StringFormatter::new("[unclosed").is_err());
+    }
+}
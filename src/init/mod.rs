@@ -1,6 +1,10 @@
 use std::ffi::OsStr;
 use std::path::Path;
-use std::{env, io};
+use std::time::{SystemTime, UNIX_EPOCH};
+use std::{env, io, process};
+
+use crate::config::StarshipConfig;
+use crate::configs::StarshipRootConfig;
 
 /* We use a two-phase init here: the first phase gives a simple command to the
 shell. This command evaluates a more complicated script using `source` and
@@ -163,12 +167,46 @@ pub fn init_main(shell_name: &str) -> io::Result<()> {
     if let Some(script) = setup_script {
         // Set up quoting for starship path in case it has spaces.
         let starship_path_string = format!("\"{}\"", starship_path);
-        let script = script.replace("::STARSHIP::", &starship_path_string);
+        let config = StarshipConfig::initialize();
+        let root_config = config.get_root_config();
+        let script = script
+            .replace("::STARSHIP::", &starship_path_string)
+            .replace(
+                "::TRANSIENT::",
+                &transient_enabled(&root_config).to_string(),
+            )
+            .replace(
+                "::RIGHT_PROMPT::",
+                &right_prompt_enabled(&root_config).to_string(),
+            )
+            .replace("::TITLE::", &root_config.title.enabled.to_string())
+            .replace("::SESSION_KEY::", &session_key());
         print!("{}", script);
     };
     Ok(())
 }
 
+/// The transient prompt hooks are only installed when a `transient_format` is configured,
+/// so that shells without one keep their default key bindings
+fn transient_enabled(config: &StarshipRootConfig) -> bool {
+    !config.transient_format.is_empty()
+}
+
+/// Shells which render the right prompt with a separate command (e.g. PowerShell) only run
+/// it when a `right_format` is configured, to save a process per prompt
+fn right_prompt_enabled(config: &StarshipRootConfig) -> bool {
+    !config.right_format.is_empty()
+}
+
+/// A key which is unique to each shell session, used to only show some messages once
+fn session_key() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_nanos())
+        .unwrap_or_default();
+    format!("{:x}{:x}", nanos, process::id())
+}
+
 /* GENERAL INIT SCRIPT NOTES
 
 Each init script will be passed as-is. Global notes for init scripts are in this
@@ -181,7 +219,11 @@ to avoid the cost of an additional shell fork every shell draw.
 Note that the init scripts are not in their final form--they are processed by
 `starship init` prior to emitting the final form. In this processing, some tokens
 are replaced, e.g. `::STARSHIP::` is replaced by the full path to the
-starship binary.
+starship binary, and `::TRANSIENT::` by `true` or `false` depending on whether a
+transient prompt is configured (likewise `::RIGHT_PROMPT::` for a right prompt, and
+`::TITLE::` for the terminal title, which is set by a hook before each command).
+`::SESSION_KEY::` is replaced by a key which is
+unique to the shell session, which is exported as `STARSHIP_SESSION_KEY`.
 */
 
 const BASH_INIT: &str = include_str!("starship.bash");
@@ -21,6 +21,15 @@ starship_preexec() {
     if [ "$PREEXEC_READY" = "true" ]; then
         PREEXEC_READY=false
         STARSHIP_START_TIME=$(::STARSHIP:: time)
+        # Show the command in the terminal's title while it runs. $BASH_COMMAND only holds
+        # the first command of a pipeline or list, so the whole line is read from the
+        # history, without its number.
+        if ::TITLE::; then
+            local STARSHIP_COMMAND
+            STARSHIP_COMMAND="$(HISTTIMEFORMAT= history 1)"
+            STARSHIP_COMMAND="${STARSHIP_COMMAND#*[0-9]  }"
+            ::STARSHIP:: prompt --title --command="${STARSHIP_COMMAND:-$BASH_COMMAND}"
+        fi
     fi
     
     : "$PREV_LAST_ARG"
@@ -28,8 +37,14 @@ starship_preexec() {
 
 # Will be run before the prompt is drawn
 starship_precmd() {
-    # Save the status, because commands in this pipeline will change $?
-    STATUS=$?
+    # Save the status and the statuses of the pipeline, because commands in this pipeline
+    # will change $? and $PIPESTATUS
+    STATUS=$? STARSHIP_PIPE_STATUS=(${PIPESTATUS[@]})
+
+    # bash-preexec runs its own commands first, but saves the statuses of the pipeline
+    if [[ ${#BP_PIPESTATUS[@]} -gt ${#STARSHIP_PIPE_STATUS[@]} ]]; then
+        STARSHIP_PIPE_STATUS=(${BP_PIPESTATUS[@]})
+    fi
 
     # Run the bash precmd function, if it's set. If not set, evaluates to no-op
     "${starship_precmd_user_func-:}"
@@ -38,10 +53,10 @@ starship_precmd() {
     if [[ $STARSHIP_START_TIME ]]; then
         STARSHIP_END_TIME=$(::STARSHIP:: time)
         STARSHIP_DURATION=$((STARSHIP_END_TIME - STARSHIP_START_TIME))
-        PS1="$(::STARSHIP:: prompt --status=$STATUS --jobs="$(jobs -p | wc -l)" --cmd-duration=$STARSHIP_DURATION)"
+        PS1="$(::STARSHIP:: prompt --status=$STATUS --pipestatus="${STARSHIP_PIPE_STATUS[*]}" --jobs="$(jobs -p | wc -l)" --cmd-duration=$STARSHIP_DURATION --terminal-width="${COLUMNS}")"
         unset STARSHIP_START_TIME
     else
-        PS1="$(::STARSHIP:: prompt --status=$STATUS --jobs="$(jobs -p | wc -l)")"
+        PS1="$(::STARSHIP:: prompt --status=$STATUS --pipestatus="${STARSHIP_PIPE_STATUS[*]}" --jobs="$(jobs -p | wc -l)" --terminal-width="${COLUMNS}")"
     fi
     PREEXEC_READY=true;  # Signal that we can safely restart the timer
 }
@@ -78,3 +93,9 @@ fi
 # Set up the start time and STARSHIP_SHELL, which controls shell-specific sequences
 STARSHIP_START_TIME=$(::STARSHIP:: time)
 export STARSHIP_SHELL="bash"
+
+# Set up the session key, which identifies this shell session
+export STARSHIP_SESSION_KEY="::SESSION_KEY::"
+
+# Set up the continuation prompt, once STARSHIP_SHELL is known so that it is escaped
+PS2="$(::STARSHIP:: prompt --continuation)"
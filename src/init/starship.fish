@@ -1,4 +1,27 @@
 function fish_prompt
+    # Save the statuses of the last pipeline first, as any command changes them
+    set -l pipe_status $pipestatus
+    switch "$fish_key_bindings"
+        case fish_hybrid_key_bindings fish_vi_key_bindings
+            set keymap "$fish_bind_mode"
+        case '*'
+            set keymap insert
+    end
+    set -l exit_code $status
+    if set -q STARSHIP_TRANSIENT
+        ::STARSHIP:: prompt --transient --status=$exit_code --keymap=$keymap --jobs=(count (jobs -p))
+        return
+    end
+    # Account for changes in variable name between v2.7 and v3.0
+    set -l starship_duration "$CMD_DURATION$cmd_duration"
+    ::STARSHIP:: prompt --status=$exit_code --pipestatus="$pipe_status" --keymap=$keymap --cmd-duration=$starship_duration --jobs=(count (jobs -p)) --terminal-width=$COLUMNS
+end
+
+function fish_right_prompt
+    set -l pipe_status $pipestatus
+    if set -q STARSHIP_TRANSIENT
+        return
+    end
     switch "$fish_key_bindings"
         case fish_hybrid_key_bindings fish_vi_key_bindings
             set keymap "$fish_bind_mode"
@@ -8,7 +31,44 @@ function fish_prompt
     set -l exit_code $status
     # Account for changes in variable name between v2.7 and v3.0
     set -l starship_duration "$CMD_DURATION$cmd_duration"
-    ::STARSHIP:: prompt --status=$exit_code --keymap=$keymap --cmd-duration=$starship_duration --jobs=(count (jobs -p))
+    ::STARSHIP:: prompt --right --status=$exit_code --pipestatus="$pipe_status" --keymap=$keymap --cmd-duration=$starship_duration --jobs=(count (jobs -p))
+end
+
+# Once a valid command line is accepted, redraw its prompt as the transient prompt so
+# that the scrollback isn't cluttered with full prompts
+if ::TRANSIENT::
+    function __starship_transient_execute
+        set -l cmd (commandline)
+        if commandline --is-valid; and test -n "$cmd"
+            set -g STARSHIP_TRANSIENT 1
+            commandline -f repaint
+        end
+        commandline -f execute
+    end
+
+    function __starship_transient_postexec --on-event fish_postexec
+        set -e -g STARSHIP_TRANSIENT
+    end
+
+    bind \r __starship_transient_execute
+    bind -M insert \r __starship_transient_execute
+end
+
+# Set the terminal's title, which fish redraws on every prompt and command. The command
+# is only passed while it runs.
+if ::TITLE::
+    function fish_title
+        if set -q argv[1]
+            ::STARSHIP:: prompt --title --command="$argv[1]"
+        else
+            ::STARSHIP:: prompt --title
+        end
+    end
+end
+
+# Redraw the prompt if the user switches vi modes, as the character shows the mode
+function __starship_mode_change --on-variable fish_bind_mode
+    commandline -f repaint
 end
 
 # disable virtualenv prompt, it breaks starship
@@ -16,3 +76,6 @@ set VIRTUAL_ENV_DISABLE_PROMPT 1
 
 function fish_mode_prompt; end
 export STARSHIP_SHELL="fish"
+
+# Set up the session key, which identifies this shell session
+export STARSHIP_SESSION_KEY="::SESSION_KEY::"
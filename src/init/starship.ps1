@@ -3,21 +3,56 @@
 # Starship assumes UTF-8
 [Console]::OutputEncoding = [System.Text.Encoding]::UTF8
 function global:prompt {
+    if ($global:STARSHIP_TRANSIENT) {
+        $global:STARSHIP_TRANSIENT = $false
+        $out = @(&::STARSHIP:: prompt --transient "--path=$PWD" --status=$lastexitcode)
+        return $out -join "`n"
+    }
+
     $out = $null
     # @ makes sure the result is an array even if single or no values are returned
     $jobs = @(Get-Job | Where-Object { $_.State -eq 'Running' }).Count
 
+    $arguments = @(
+        "prompt"
+        "--path=$PWD"
+        "--status=$lastexitcode"
+        "--jobs=$jobs"
+        "--terminal-width=$($Host.UI.RawUI.WindowSize.Width)"
+    )
+
     if ($lastCmd = Get-History -Count 1) {
         $duration = [math]::Round(($lastCmd.EndExecutionTime - $lastCmd.StartExecutionTime).TotalMilliseconds)
-        # & ensures the path is interpreted as something to execute
-        $out = @(&::STARSHIP:: prompt "--path=$PWD" --status=$lastexitcode --jobs=$jobs --cmd-duration=$duration)
-    } else {
-        $out = @(&::STARSHIP:: prompt "--path=$PWD" --status=$lastexitcode --jobs=$jobs)
+        $arguments += "--cmd-duration=$duration"
+    }
+
+    # & ensures the path is interpreted as something to execute
+    $out = @(&::STARSHIP:: @arguments)
+    # The right prompt moves the cursor to the right edge of the last line by itself
+    $right = @()
+    if ("::RIGHT_PROMPT::" -eq "true") {
+        $right = @(&::STARSHIP:: @arguments --right)
     }
 
     # Convert stdout (array of lines) to expected return type string
     # `n is an escaped newline
-    $out -join "`n"
+    ($out -join "`n") + ($right -join "")
+}
+
+# Once a command line is accepted, redraw its prompt as the transient prompt so that
+# the scrollback isn't cluttered with full prompts
+if ("::TRANSIENT::" -eq "true") {
+    Set-PSReadLineKeyHandler -Key Enter -ScriptBlock {
+        $global:STARSHIP_TRANSIENT = $true
+        [Microsoft.PowerShell.PSConsoleReadLine]::InvokePrompt()
+        [Microsoft.PowerShell.PSConsoleReadLine]::AcceptLine()
+    }
 }
 
 $ENV:STARSHIP_SHELL = "powershell"
+
+# Set up the session key, which identifies this shell session
+$ENV:STARSHIP_SESSION_KEY = "::SESSION_KEY::"
+
+# Set up the continuation prompt
+Set-PSReadLineOption -ContinuationPrompt (@(&::STARSHIP:: prompt --continuation) -join "")
@@ -1,7 +1,13 @@
 // Lib is present to allow for benchmarking
+pub mod cache;
+pub mod color;
 pub mod config;
+pub mod config_check;
+pub mod config_print;
+pub mod config_schema;
 pub mod configs;
 pub mod context;
+pub mod formatter;
 pub mod module;
 pub mod modules;
 pub mod print;
@@ -4,13 +4,20 @@ use std::time::SystemTime;
 extern crate clap;
 
 mod bug_report;
+mod cache;
+mod color;
 mod config;
+mod config_check;
+mod config_print;
+mod config_schema;
 mod configs;
 mod configure;
 mod context;
+mod formatter;
 mod init;
 mod module;
 mod modules;
+mod presets;
 mod print;
 mod segment;
 mod utils;
@@ -28,6 +35,14 @@ fn main() {
         .help("The status code of the previously run command")
         .takes_value(true);
 
+    let pipestatus_arg = Arg::with_name("pipestatus")
+        .long("pipestatus")
+        .value_name("PIPESTATUS")
+        .help(
+            "The status codes of each command of the previously run pipeline, separated by spaces",
+        )
+        .takes_value(true);
+
     let path_arg = Arg::with_name("path")
         .short("p")
         .long("path")
@@ -64,68 +79,177 @@ fn main() {
         .help("The number of currently running jobs")
         .takes_value(true);
 
+    let terminal_width_arg = Arg::with_name("terminal_width")
+        .short("w")
+        .long("terminal-width")
+        .value_name("WIDTH")
+        .help("The width of the current interactive terminal")
+        .takes_value(true);
+
     let init_scripts_arg = Arg::with_name("print_full_init")
         .long("print-full-init")
         .help("Print the main initialization script (as opposed to the init stub)");
 
-    let matches =
-        App::new("starship")
-            .about("The cross-shell prompt for astronauts. ☄🌌️")
-            // pull the version number from Cargo.toml
-            .version(crate_version!())
-            // pull the authors from Cargo.toml
-            .author(crate_authors!())
-            .after_help("https://github.com/starship/starship")
-            .setting(AppSettings::SubcommandRequiredElseHelp)
-            .subcommand(
-                SubCommand::with_name("init")
-                    .about("Prints the shell function used to execute starship")
-                    .arg(&shell_arg)
-                    .arg(&init_scripts_arg),
-            )
-            .subcommand(
-                SubCommand::with_name("prompt")
-                    .about("Prints the full starship prompt")
-                    .arg(&status_code_arg)
-                    .arg(&path_arg)
-                    .arg(&cmd_duration_arg)
-                    .arg(&keymap_arg)
-                    .arg(&jobs_arg),
-            )
-            .subcommand(
-                SubCommand::with_name("module")
-                    .about("Prints a specific prompt module")
-                    .arg(
-                        Arg::with_name("name")
-                            .help("The name of the module to be printed")
-                            .required(true)
-                            .required_unless("list"),
-                    )
-                    .arg(
-                        Arg::with_name("list")
-                            .short("l")
-                            .long("list")
-                            .help("List out all supported modules"),
-                    )
-                    .arg(&status_code_arg)
-                    .arg(&path_arg)
-                    .arg(&cmd_duration_arg)
-                    .arg(&keymap_arg)
-                    .arg(&jobs_arg),
-            )
-            .subcommand(SubCommand::with_name("configure").about("Edit the starship configuration"))
-            .subcommand(SubCommand::with_name("bug-report").about(
+    let matches = App::new("starship")
+        .about("The cross-shell prompt for astronauts. ☄🌌️")
+        // pull the version number from Cargo.toml
+        .version(crate_version!())
+        // pull the authors from Cargo.toml
+        .author(crate_authors!())
+        .after_help("https://github.com/starship/starship")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .subcommand(
+            SubCommand::with_name("init")
+                .about("Prints the shell function used to execute starship")
+                .arg(&shell_arg)
+                .arg(&init_scripts_arg),
+        )
+        .subcommand(
+            SubCommand::with_name("prompt")
+                .about("Prints the full starship prompt")
+                .arg(
+                    Arg::with_name("right")
+                        .long("right")
+                        .help("Print the right prompt (instead of the standard left prompt)"),
+                )
+                .arg(
+                    Arg::with_name("transient")
+                        .long("transient")
+                        .help("Print the transient prompt, which replaces previous prompts")
+                        .conflicts_with("right"),
+                )
+                .arg(
+                    Arg::with_name("continuation")
+                        .long("continuation")
+                        .help("Print the continuation prompt (instead of the standard left prompt)")
+                        .conflicts_with_all(&["right", "transient"]),
+                )
+                .arg(
+                    Arg::with_name("json")
+                        .long("json")
+                        .help("Print the computed modules as JSON (instead of the prompt)")
+                        .conflicts_with_all(&["right", "transient", "continuation"]),
+                )
+                .arg(
+                    Arg::with_name("title")
+                        .long("title")
+                        .help("Print the sequence which sets the terminal's title (instead of the prompt)")
+                        .conflicts_with_all(&["right", "transient", "continuation", "json"]),
+                )
+                .arg(
+                    Arg::with_name("command")
+                        .long("command")
+                        .value_name("COMMAND")
+                        .help("The command which is about to run, for the title")
+                        .takes_value(true)
+                        .requires("title"),
+                )
+                .arg(&status_code_arg)
+                .arg(&pipestatus_arg)
+                .arg(&path_arg)
+                .arg(&cmd_duration_arg)
+                .arg(&keymap_arg)
+                .arg(&jobs_arg)
+                .arg(&terminal_width_arg),
+        )
+        .subcommand(
+            SubCommand::with_name("module")
+                .about("Prints a specific prompt module")
+                .arg(
+                    Arg::with_name("name")
+                        .help("The name of the module to be printed")
+                        .required(true)
+                        .required_unless("list"),
+                )
+                .arg(
+                    Arg::with_name("list")
+                        .short("l")
+                        .long("list")
+                        .help("List out all supported modules"),
+                )
+                .arg(&status_code_arg)
+                .arg(&pipestatus_arg)
+                .arg(&path_arg)
+                .arg(&cmd_duration_arg)
+                .arg(&keymap_arg)
+                .arg(&jobs_arg),
+        )
+        .subcommand(SubCommand::with_name("configure").about("Edit the starship configuration"))
+        .subcommand(
+            SubCommand::with_name("print-config")
+                .about("Prints the effective configuration, with the defaults of unset options")
+                .arg(
+                    Arg::with_name("name")
+                        .help("The names of the modules to print (all of them by default)")
+                        .multiple(true),
+                )
+                .arg(
+                    Arg::with_name("default")
+                        .long("default")
+                        .help("Print the default configuration, ignoring the configuration file"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("preset")
+                .about("Prints one of the configuration presets bundled with starship")
+                .arg(
+                    Arg::with_name("name")
+                        .help("The name of the preset (the presets are listed if omitted)"),
+                )
+                .arg(
+                    Arg::with_name("list")
+                        .short("l")
+                        .long("list")
+                        .help("List out all presets"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("config")
+                .about("Inspect the starship configuration")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    SubCommand::with_name("check")
+                        .about("Check the configuration file for mistakes"),
+                )
+                .subcommand(
+                    SubCommand::with_name("schema")
+                        .about("Print a JSON schema of the configuration file"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("bug-report").about(
                 "Create a pre-populated GitHub issue with information about your configuration",
-            ))
-            .subcommand(
-                SubCommand::with_name("time")
-                    .about("Prints time in milliseconds")
-                    .settings(&[AppSettings::Hidden]),
-            )
-            .subcommand(
-                SubCommand::with_name("explain").about("Explains the currently showing modules"),
-            )
-            .get_matches();
+            ),
+        )
+        .subcommand(
+            SubCommand::with_name("time")
+                .about("Prints time in milliseconds")
+                .settings(&[AppSettings::Hidden]),
+        )
+        .subcommand(
+            SubCommand::with_name("explain").about("Explains the currently showing modules"),
+        )
+        .subcommand(
+            SubCommand::with_name("cache")
+                .about("Manage the cache of external command outputs (e.g. tool versions)")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(SubCommand::with_name("clear").about("Remove every cached output"))
+                .subcommand(
+                    SubCommand::with_name("stats")
+                        .about("Print the size and contents of the cache"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("timings")
+                .about("Prints how long each module of the prompt takes to compute")
+                .arg(&status_code_arg)
+                .arg(&pipestatus_arg)
+                .arg(&path_arg)
+                .arg(&cmd_duration_arg)
+                .arg(&keymap_arg)
+                .arg(&jobs_arg),
+        )
+        .get_matches();
 
     match matches.subcommand() {
         ("init", Some(sub_m)) => {
@@ -150,6 +274,25 @@ fn main() {
             }
         }
         ("configure", Some(_)) => configure::edit_configuration(),
+        ("print-config", Some(sub_m)) => {
+            let modules = sub_m
+                .values_of("name")
+                .map(|names| names.collect::<Vec<&str>>())
+                .unwrap_or_default();
+            config_print::print_config(&modules, sub_m.is_present("default"));
+        }
+        ("preset", Some(sub_m)) => {
+            if sub_m.is_present("list") {
+                presets::print_list();
+            } else {
+                presets::print_preset(sub_m.value_of("name"));
+            }
+        }
+        ("config", Some(sub_m)) => match sub_m.subcommand_name() {
+            Some("check") => config_check::check(),
+            Some("schema") => config_schema::print_schema(),
+            _ => {}
+        },
         ("bug-report", Some(_)) => bug_report::create(),
         ("time", _) => {
             match SystemTime::now()
@@ -161,6 +304,12 @@ fn main() {
             }
         }
         ("explain", Some(sub_m)) => print::explain(sub_m.clone()),
+        ("timings", Some(sub_m)) => print::timings(sub_m.clone()),
+        ("cache", Some(sub_m)) => match sub_m.subcommand_name() {
+            Some("clear") => cache::clear(),
+            Some("stats") => cache::stats(),
+            _ => {}
+        },
         _ => {}
     }
 }
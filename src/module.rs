@@ -1,8 +1,14 @@
-use crate::config::SegmentConfig;
+use crate::color;
+use crate::config::{parse_style_string, style_to_string, SegmentConfig};
+use crate::formatter::StringFormatter;
 use crate::segment::Segment;
-use ansi_term::Style;
 use ansi_term::{ANSIString, ANSIStrings};
+use ansi_term::{Color, Style};
+use serde_json::json;
+use std::collections::HashMap;
 use std::fmt;
+use std::time::Duration;
+use unicode_width::UnicodeWidthChar;
 
 // List of all modules
 // Keep these ordered alphabetically.
@@ -17,6 +23,7 @@ pub const ALL_MODULES: &[&str] = &[
     "directory",
     "dotnet",
     "env_var",
+    "fill",
     "git_branch",
     "git_commit",
     "git_state",
@@ -36,6 +43,7 @@ pub const ALL_MODULES: &[&str] = &[
     "ruby",
     "rust",
     "php",
+    "status",
     "terraform",
     "time",
     "username",
@@ -64,11 +72,25 @@ pub struct Module<'a> {
 
     /// The suffix used to separate the current module from the next one.
     suffix: Affix,
+
+    /// The user-provided format string, which replaces the prefix, segments and suffix.
+    format: Option<&'a str>,
+
+    /// Values published for use in the format string, besides the segments.
+    variables: HashMap<String, String>,
+
+    /// How long the module took to compute.
+    duration: Duration,
+
+    /// The background of the whole module, as shown in a powerline prompt.
+    background: Option<Color>,
 }
 
 impl<'a> Module<'a> {
     /// Creates a module with no segments.
     pub fn new(name: &str, desc: &str, config: Option<&'a toml::Value>) -> Module<'a> {
+        let format = config.and_then(|config| config.get("format")?.as_str());
+
         Module {
             config,
             _name: name.to_string(),
@@ -77,9 +99,18 @@ impl<'a> Module<'a> {
             prefix: Affix::default_prefix(name),
             segments: Vec::new(),
             suffix: Affix::default_suffix(name),
+            format,
+            variables: HashMap::new(),
+            duration: Duration::default(),
+            background: None,
         }
     }
 
+    /// Ignore the user-provided format string, e.g. when it is the legacy option of a module
+    pub fn clear_format(&mut self) {
+        self.format = None;
+    }
+
     /// Get a reference to a newly created segment in the module
     pub fn create_segment(&mut self, name: &str, segment_config: &SegmentConfig) -> &mut Segment {
         let mut segment = Segment::new(name);
@@ -100,13 +131,101 @@ impl<'a> Module<'a> {
         &self.description
     }
 
+    /// Publish a value to be used as a variable in the module's format string.
+    ///
+    /// Every segment can be used as a variable by its name, so this is only needed for
+    /// values that aren't shown by default (e.g. counts which are hidden unless enabled).
+    pub fn set_variable<T>(&mut self, name: &str, value: T) -> &mut Module<'a>
+    where
+        T: Into<String>,
+    {
+        self.variables.insert(name.to_string(), value.into());
+        self
+    }
+
     /// Whether a module has non-empty segments
     pub fn is_empty(&self) -> bool {
-        self.segments.iter().all(|segment| segment.is_empty())
+        self.rendered_segments()
+            .iter()
+            .all(|segment| segment.is_empty())
     }
 
-    pub fn get_segments(&self) -> Vec<&str> {
-        self.segments.iter().map(Segment::get_value).collect()
+    pub fn get_segments(&self) -> Vec<String> {
+        self.rendered_segments()
+            .iter()
+            .map(|segment| segment.get_value().to_owned())
+            .collect()
+    }
+
+    /// The segments to be displayed, produced by the format string if one is set.
+    fn rendered_segments(&self) -> Vec<Segment> {
+        let format = match self.format {
+            Some(format) => format,
+            None => return self.segments.clone(),
+        };
+
+        match StringFormatter::new(format) {
+            Ok(formatter) => formatter
+                .map(|name| self.variables.get(name))
+                .map_variables_to_segments(|name| {
+                    let segments = self
+                        .segments
+                        .iter()
+                        .filter(|segment| segment.get_name() == name)
+                        .cloned()
+                        .collect::<Vec<Segment>>();
+                    Some(segments).filter(|segments| !segments.is_empty())
+                })
+                .map_style(|name| match name {
+                    "style" => Some(self.style),
+                    _ => parse_style_string(self.config?.get(name)?.as_str()?),
+                })
+                .parse(None),
+            Err(error) => {
+                log::warn!(
+                    "Unable to parse format string of module {}: {}",
+                    self._name,
+                    error
+                );
+                self.segments.clone()
+            }
+        }
+    }
+
+    /// Set how long the module took to compute
+    pub fn set_duration(&mut self, duration: Duration) -> &mut Module<'a> {
+        self.duration = duration;
+        self
+    }
+
+    /// Serialize the module, along with its displayed segments and affixes.
+    /// The affixes are empty when a format string is set, as they are not shown.
+    pub fn to_json(&self) -> serde_json::Value {
+        let segments = self
+            .rendered_segments()
+            .iter()
+            .map(|segment| {
+                json!({
+                    "name": segment.get_name(),
+                    "value": segment.get_value(),
+                    "style": segment.get_style().as_ref().map(style_to_string),
+                })
+            })
+            .collect::<Vec<serde_json::Value>>();
+
+        let affix_to_json = |affix: &Affix| match self.format {
+            Some(_) => json!({ "value": "", "style": "" }),
+            None => json!({ "value": affix.value, "style": style_to_string(&affix.style) }),
+        };
+
+        json!({
+            "name": self._name,
+            "description": self.description,
+            "prefix": affix_to_json(&self.prefix),
+            "segments": segments,
+            "suffix": affix_to_json(&self.suffix),
+            "duration_ms": self.duration.as_secs_f64() * 1000.0,
+        })
     }
 
     /// Get the module's prefix
@@ -130,6 +249,18 @@ impl<'a> Module<'a> {
         self
     }
 
+    /// Sets the background of every segment and affix which doesn't have a background of
+    /// its own.
+    pub fn set_background(&mut self, background: Option<Color>) -> &mut Module<'a> {
+        self.background = background;
+        self
+    }
+
+    /// Get the background of the module, if one is set
+    pub fn get_background(&self) -> Option<Color> {
+        self.background
+    }
+
     /// Returns a vector of colored ANSIString elements to be later used with
     /// `ANSIStrings()` to optimize ANSI codes
     pub fn ansi_strings(&self) -> Vec<ANSIString> {
@@ -137,29 +268,95 @@ impl<'a> Module<'a> {
     }
 
     pub fn ansi_strings_for_prompt(&self, is_prompt: bool) -> Vec<ANSIString> {
-        let mut ansi_strings = self
-            .segments
-            .iter()
-            .map(Segment::ansi_string)
-            .collect::<Vec<ANSIString>>();
-
-        ansi_strings.insert(0, self.prefix.ansi_string());
-        ansi_strings.push(self.suffix.ansi_string());
+        let mut ansi_strings = self.paint_segments(self.rendered_segments());
+
+        // A format string takes the place of the prefix and suffix
+        if self.format.is_some() {
+            ansi_strings.insert(0, ANSIString::from(""));
+            ansi_strings.push(ANSIString::from(""));
+        } else {
+            ansi_strings.insert(0, self.prefix.ansi_string_on(self.background));
+            ansi_strings.push(self.suffix.ansi_string_on(self.background));
+        }
 
         if is_prompt {
-            let shell = std::env::var("STARSHIP_SHELL").unwrap_or_default();
-            ansi_strings = match shell.as_str() {
-                "bash" => ansi_strings_modified(ansi_strings, shell),
-                "zsh" => ansi_strings_modified(ansi_strings, shell),
-                _ => ansi_strings,
-            };
+            ansi_strings = ansi_strings_for_shell(ansi_strings);
         }
 
         ansi_strings
     }
 
-    pub fn to_string_without_prefix(&self) -> String {
-        ANSIStrings(&self.ansi_strings()[1..]).to_string()
+    /// Renders the module's segments repeated until they take up `width` columns, without
+    /// its prefix and suffix. This is how the `fill` module pads a line of the prompt.
+    pub fn to_string_filled(&self, width: usize) -> String {
+        let segments = self.rendered_segments();
+        // Consecutive characters of a segment are painted together, as a run
+        let mut runs: Vec<(usize, String)> = Vec::new();
+        let mut filled_width = 0;
+
+        // Without any visible character, repeating the segments would never fill the line
+        let has_width = segments
+            .iter()
+            .flat_map(|segment| segment.get_value().chars())
+            .any(|c| c.width().unwrap_or(0) > 0);
+        if has_width {
+            let chars = segments
+                .iter()
+                .enumerate()
+                .cycle()
+                .flat_map(|(i, segment)| segment.get_value().chars().map(move |c| (i, c)));
+            for (i, c) in chars {
+                let char_width = c.width().unwrap_or(0);
+                if filled_width + char_width > width {
+                    break;
+                }
+                filled_width += char_width;
+                match runs.last_mut() {
+                    Some((last, run)) if *last == i => run.push(c),
+                    _ => runs.push((i, c.to_string())),
+                }
+            }
+        }
+
+        let mut filled = runs
+            .into_iter()
+            .map(|(i, run)| {
+                let mut segment = segments[i].clone();
+                segment.set_value(run);
+                segment
+            })
+            .collect::<Vec<Segment>>();
+
+        // A wide character may not fit in the last column, which is left blank
+        let mut padding = Segment::new("_padding");
+        padding.set_value(" ".repeat(width - filled_width));
+        filled.push(padding);
+
+        let ansi_strings = ansi_strings_for_shell(self.paint_segments(filled));
+        ANSIStrings(&ansi_strings).to_string()
+    }
+
+    /// Paint the segments of the module, on the module's background if it has one
+    fn paint_segments(&self, segments: Vec<Segment>) -> Vec<ANSIString<'static>> {
+        segments
+            .into_iter()
+            .map(|mut segment| {
+                if self.background.is_some() {
+                    let style = segment.get_style().unwrap_or_default();
+                    segment.set_style(with_background(style, self.background));
+                }
+                segment.into_ansi_string()
+            })
+            .collect()
+    }
+
+    /// Renders the module, leaving out its prefix and/or suffix when they are not wanted
+    /// (e.g. when the module starts a line or is followed by literal text)
+    pub fn to_string_with_affixes(&self, prefix: bool, suffix: bool) -> String {
+        let ansi_strings = self.ansi_strings();
+        let start = if prefix { 0 } else { 1 };
+        let end = ansi_strings.len() - if suffix { 0 } else { 1 };
+        ANSIStrings(&ansi_strings[start..end]).to_string()
     }
 }
 
@@ -170,48 +367,85 @@ impl<'a> fmt::Display for Module<'a> {
     }
 }
 
+/// Wrap the escape sequences in `ansi_strings` for the current shell (`$STARSHIP_SHELL`),
+/// so that they are not counted towards the length of the prompt.
+pub fn ansi_strings_for_shell(ansi_strings: Vec<ANSIString>) -> Vec<ANSIString> {
+    let shell = std::env::var("STARSHIP_SHELL").unwrap_or_default();
+    match shell.as_str() {
+        "bash" => ansi_strings_modified(ansi_strings, shell),
+        "zsh" => ansi_strings_modified(ansi_strings, shell),
+        _ => ansi_strings,
+    }
+}
+
 /// Many shells cannot deal with raw unprintable characters (like ANSI escape sequences) and
 /// miscompute the cursor position as a result, leading to strange visual bugs. Here, we wrap these
 /// characters in shell-specific escape codes to indicate to the shell that they are zero-length.
 fn ansi_strings_modified(ansi_strings: Vec<ANSIString>, shell: String) -> Vec<ANSIString> {
     const ESCAPE_BEGIN: char = '\u{1b}';
-    const MAYBE_ESCAPE_END: char = 'm';
+    let (zero_width_begin, zero_width_end) = match shell.as_str() {
+        "bash" => ("\u{5c}\u{5b}", "\u{5c}\u{5d}"), // => \[ and \]
+        "zsh" => ("\u{25}\u{7b}", "\u{25}\u{7d}"),  // => %{ and %}
+        _ => ("", ""),
+    };
     ansi_strings
         .iter()
         .map(|ansi| {
-            let mut escaped = false;
-            let final_string: String = ansi
-                .to_string()
-                .chars()
-                .map(|x| match x {
-                    ESCAPE_BEGIN => {
-                        escaped = true;
-                        match shell.as_str() {
-                            "bash" => String::from("\u{5c}\u{5b}\u{1b}"), // => \[ESC
-                            "zsh" => String::from("\u{25}\u{7b}\u{1b}"),  // => %{ESC
-                            _ => x.to_string(),
+            let value = ansi.to_string();
+            let mut final_string = String::with_capacity(value.len());
+            let mut chars = value.chars().peekable();
+            while let Some(x) = chars.next() {
+                if x != ESCAPE_BEGIN {
+                    final_string.push(x);
+                    continue;
+                }
+
+                final_string.push_str(zero_width_begin);
+                final_string.push(x);
+                match chars.next() {
+                    // A control sequence (e.g. a style) ends with a character from `@` to `~`
+                    Some('[') => {
+                        final_string.push('[');
+                        for x in chars.by_ref() {
+                            final_string.push(x);
+                            if ('@'..='~').contains(&x) {
+                                break;
+                            }
                         }
                     }
-                    MAYBE_ESCAPE_END => {
-                        if escaped {
-                            escaped = false;
-                            match shell.as_str() {
-                                "bash" => String::from("m\u{5c}\u{5d}"), // => m\]
-                                "zsh" => String::from("m\u{25}\u{7d}"),  // => m%}
-                                _ => x.to_string(),
+                    // An operating system command (e.g. the working directory) ends with BEL
+                    // or with `ESC \`
+                    Some(']') => {
+                        final_string.push(']');
+                        while let Some(x) = chars.next() {
+                            final_string.push(x);
+                            if x == '\u{7}' {
+                                break;
+                            }
+                            if x == ESCAPE_BEGIN && chars.next_if_eq(&'\\').is_some() {
+                                final_string.push('\\');
+                                break;
                             }
-                        } else {
-                            x.to_string()
                         }
                     }
-                    _ => x.to_string(),
-                })
-                .collect();
+                    Some(x) => final_string.push(x),
+                    None => {}
+                }
+                final_string.push_str(zero_width_end);
+            }
             ANSIString::from(final_string)
         })
         .collect::<Vec<ANSIString>>()
 }
 
+/// Set the background of a style, unless it already has one
+fn with_background(style: Style, background: Option<Color>) -> Style {
+    Style {
+        background: style.background.or(background),
+        ..style
+    }
+}
+
 /// Module affixes are to be used for the prefix or suffix of a module.
 pub struct Affix {
     /// The affix's name, to be used in configuration and logging.
@@ -263,7 +497,13 @@ impl Affix {
 
     /// Generates the colored ANSIString output.
     pub fn ansi_string(&self) -> ANSIString {
-        self.style.paint(&self.value)
+        self.ansi_string_on(None)
+    }
+
+    /// Generates the colored ANSIString output, on `background` unless the affix has a
+    /// background of its own.
+    pub fn ansi_string_on(&self, background: Option<Color>) -> ANSIString<'_> {
+        color::adapt(with_background(self.style, background)).paint(&self.value)
     }
 }
 
@@ -289,6 +529,10 @@ mod tests {
             prefix: Affix::default_prefix(name),
             segments: Vec::new(),
             suffix: Affix::default_suffix(name),
+            format: None,
+            variables: HashMap::new(),
+            duration: Duration::default(),
+            background: None,
         };
 
         assert!(module.is_empty());
@@ -306,8 +550,68 @@ mod tests {
             prefix: Affix::default_prefix(name),
             segments: vec![Segment::new("test_segment")],
             suffix: Affix::default_suffix(name),
+            format: None,
+            variables: HashMap::new(),
+            duration: Duration::default(),
+            background: None,
         };
 
         assert!(module.is_empty());
     }
+
+    #[test]
+    fn test_escape_sequences_are_zero_width() {
+        let value = "\u{1b}[1;32m❯\u{1b}[0m\u{1b}]7;file://host/tmp\u{1b}\\\u{1b}]133;A\u{7}";
+        let escape = |shell: &str| {
+            ansi_strings_modified(vec![ANSIString::from(value)], shell.to_string())[0].to_string()
+        };
+
+        assert_eq!(
+            escape("bash"),
+            "\\[\u{1b}[1;32m\\]❯\\[\u{1b}[0m\\]\\[\u{1b}]7;file://host/tmp\u{1b}\\\\]\\[\u{1b}]133;A\u{7}\\]"
+        );
+        assert_eq!(
+            escape("zsh"),
+            "%{\u{1b}[1;32m%}❯%{\u{1b}[0m%}%{\u{1b}]7;file://host/tmp\u{1b}\\%}%{\u{1b}]133;A\u{7}%}"
+        );
+    }
+
+    #[test]
+    fn test_module_background() {
+        let mut module = Module::new("unit_test", "This is a unit test", None);
+        module.create_segment("plain", &SegmentConfig::new("plain"));
+        module
+            .create_segment("styled", &SegmentConfig::new("styled"))
+            .set_style(Color::Red.on(Color::Green));
+        module.set_background(Some(Color::Blue));
+
+        let on_blue = Style::new().on(Color::Blue);
+        let expected = ANSIStrings(&[
+            on_blue.paint("via "),
+            on_blue.paint("plain"),
+            Color::Red.on(Color::Green).paint("styled"),
+            on_blue.paint(" "),
+        ])
+        .to_string();
+        assert_eq!(module.to_string(), expected);
+    }
+
+    #[test]
+    fn test_module_filled() {
+        let mut module = Module::new("unit_test", "This is a unit test", None);
+        module
+            .create_segment("a", &SegmentConfig::new("-"))
+            .set_style(Color::Red);
+        module.create_segment("b", &SegmentConfig::new("🚀"));
+
+        let expected = ANSIStrings(&[
+            Color::Red.paint("-"),
+            ANSIString::from("🚀"),
+            Color::Red.paint("-"),
+            ANSIString::from(" "),
+        ])
+        .to_string();
+        assert_eq!(module.to_string_filled(5), expected);
+        assert_eq!(module.to_string_filled(0), "");
+    }
 }
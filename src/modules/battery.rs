@@ -28,32 +28,36 @@ pub fn module<'a>(context: &'a Context) -> Option<Module<'a>> {
         module.set_style(display_style.style);
         module.get_prefix().set_value("");
 
-        match state {
+        let symbol = match state {
             battery::State::Full => {
-                module.create_segment("full_symbol", &battery_config.full_symbol);
+                Some(module.create_segment("full_symbol", &battery_config.full_symbol))
             }
             battery::State::Charging => {
-                module.create_segment("charging_symbol", &battery_config.charging_symbol);
-            }
-            battery::State::Discharging => {
-                module.create_segment("discharging_symbol", &battery_config.discharging_symbol);
+                Some(module.create_segment("charging_symbol", &battery_config.charging_symbol))
             }
+            battery::State::Discharging => Some(
+                module.create_segment("discharging_symbol", &battery_config.discharging_symbol),
+            ),
             battery::State::Unknown => {
                 log::debug!("Unknown detected");
-                if let Some(unknown_symbol) = battery_config.unknown_symbol {
-                    module.create_segment("unknown_symbol", &unknown_symbol);
-                }
-            }
-            battery::State::Empty => {
-                if let Some(empty_symbol) = battery_config.empty_symbol {
-                    module.create_segment("empty_symbol", &empty_symbol);
-                }
+                battery_config
+                    .unknown_symbol
+                    .map(|unknown_symbol| module.create_segment("unknown_symbol", &unknown_symbol))
             }
+            battery::State::Empty => battery_config
+                .empty_symbol
+                .map(|empty_symbol| module.create_segment("empty_symbol", &empty_symbol)),
             _ => {
                 log::debug!("Unhandled battery state `{}`", state);
                 return None;
             }
         }
+        .map(|segment| segment.get_value().to_owned());
+
+        // Whichever symbol matches the current state is available as `$symbol`
+        if let Some(symbol) = symbol {
+            module.set_variable("symbol", symbol);
+        }
 
         let mut percent_string = Vec::<String>::with_capacity(2);
         // Round the percentage to a whole number
@@ -10,13 +10,19 @@ use crate::configs::character::CharacterConfig;
 /// (green by default)
 /// - If the exit-code was anything else, the arrow will be formatted with
 /// `style_failure` (red by default)
+///
+/// In the replace, replace-one, visual and operator-pending vi modes, the character
+/// is formatted with the style of the mode instead.
 pub fn module<'a>(context: &'a Context) -> Option<Module<'a>> {
     enum ShellEditMode {
         Normal,
         Insert,
+        Replace,
+        ReplaceOne,
+        Visual,
+        OperatorPending,
     };
     const ASSUMED_MODE: ShellEditMode = ShellEditMode::Insert;
-    // TODO: extend config to more modes
 
     let mut module = context.new_module("character");
     let config: CharacterConfig = CharacterConfig::try_load(module.config);
@@ -35,8 +41,14 @@ pub fn module<'a>(context: &'a Context) -> Option<Module<'a>> {
     // Unfortunately, this is also the name of the non-vi default mode.
     // We do some environment detection in src/init.rs to translate.
     // The result: in non-vi fish, keymap is always reported as "insert"
+    // zsh has no keymap for replace mode, so src/init/starship.zsh reports it as
+    // "vireplace" when the insert keymap is in overwrite mode.
     let mode = match (shell.as_str(), keymap.as_str()) {
         ("fish", "default") | ("zsh", "vicmd") => ShellEditMode::Normal,
+        ("fish", "replace") | ("zsh", "vireplace") => ShellEditMode::Replace,
+        ("fish", "replace_one") => ShellEditMode::ReplaceOne,
+        ("fish", "visual") | ("zsh", "visual") => ShellEditMode::Visual,
+        ("zsh", "viopp") => ShellEditMode::OperatorPending,
         _ => ASSUMED_MODE,
     };
 
@@ -54,6 +66,22 @@ pub fn module<'a>(context: &'a Context) -> Option<Module<'a>> {
         match mode {
             ShellEditMode::Normal => module.create_segment("vicmd_symbol", &config.vicmd_symbol),
             ShellEditMode::Insert => module.create_segment("symbol", &config.symbol),
+            ShellEditMode::Replace => {
+                module.set_style(config.style_replace);
+                module.create_segment("replace_symbol", &config.replace_symbol)
+            }
+            ShellEditMode::ReplaceOne => {
+                module.set_style(config.style_replace_one);
+                module.create_segment("replace_one_symbol", &config.replace_one_symbol)
+            }
+            ShellEditMode::Visual => {
+                module.set_style(config.style_visual);
+                module.create_segment("visual_symbol", &config.visual_symbol)
+            }
+            ShellEditMode::OperatorPending => {
+                module.set_style(config.style_operator_pending);
+                module.create_segment("operator_pending_symbol", &config.operator_pending_symbol)
+            }
         }
     };
 
@@ -41,7 +41,7 @@ pub fn module<'a>(context: &'a Context) -> Option<Module<'a>> {
         config.prefix,
         render_time(elapsed, config.show_milliseconds)
     );
-    module.create_segment("cmd_duration", &SegmentConfig::new(&cmd_duration_stacked));
+    module.create_segment("duration", &SegmentConfig::new(&cmd_duration_stacked));
     module.get_prefix().set_value("");
 
     Some(module)
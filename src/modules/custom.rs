@@ -0,0 +1,76 @@
+use super::{Context, Module, RootModuleConfig, SegmentConfig};
+
+use crate::configs::custom::CustomConfig;
+use crate::utils::{self, CommandOutput};
+
+/// Creates a custom module, named e.g. `custom.deploy`, with the output of its command
+///
+/// Will display the output of the command if all of the following criteria are met:
+///     - The command succeeds with some output
+///     - No detection option is set, or either of the following is true:
+///         - The current directory contains one of the files, extensions or directories
+///         - The `when` command succeeds
+pub fn module<'a>(name: &str, context: &'a Context) -> Option<Module<'a>> {
+    let module_config = context.config.get_module_config(name);
+    let config = CustomConfig::try_load(module_config);
+    if config.disabled || config.command.is_empty() {
+        return None;
+    }
+
+    let has_scan_criteria =
+        !config.files.is_empty() || !config.extensions.is_empty() || !config.directories.is_empty();
+    if has_scan_criteria || config.when.is_some() {
+        let is_match = has_scan_criteria
+            && context
+                .try_begin_scan()?
+                .set_files(&config.files)
+                .set_extensions(&config.extensions)
+                .set_folders(&config.directories)
+                .is_match();
+        let is_match = is_match
+            || match config.when {
+                Some(when) => exec_shell(&config.shell, when).is_some(),
+                None => false,
+            };
+        if !is_match {
+            return None;
+        }
+    }
+
+    let output = exec_shell(&config.shell, config.command)?;
+    let output = output.stdout.trim();
+    if output.is_empty() {
+        return None;
+    }
+
+    let mut module = Module::new(name, config.description, module_config);
+    module.set_style(config.style);
+    module.get_prefix().set_value(config.prefix);
+    module.get_suffix().set_value(config.suffix);
+
+    if let Some(symbol) = config.symbol {
+        module.create_segment("symbol", &symbol);
+    }
+    module.create_segment("output", &SegmentConfig::new(output));
+
+    Some(module)
+}
+
+/// Run a command with the configured shell, which is given the command as its last
+/// argument. `sh -c` is used by default, or `cmd /C` on Windows.
+fn exec_shell(shell: &[&str], command: &str) -> Option<CommandOutput> {
+    let default_shell: &[&str] = if cfg!(windows) {
+        &["cmd", "/C"]
+    } else {
+        &["sh", "-c"]
+    };
+    let shell = if shell.is_empty() {
+        default_shell
+    } else {
+        shell
+    };
+
+    let mut args = shell[1..].to_vec();
+    args.push(command);
+    utils::exec_cmd(shell[0], &args)
+}
@@ -2,11 +2,11 @@ use std::ffi::OsStr;
 use std::iter::Iterator;
 use std::ops::Deref;
 use std::path::{Path, PathBuf};
-use std::process::{Command, Stdio};
 use std::str;
 
 use super::{Context, Module, RootModuleConfig};
 use crate::configs::dotnet::DotnetConfig;
+use crate::utils;
 
 type JValue = serde_json::Value;
 
@@ -200,17 +200,17 @@ fn map_str_to_lower(value: Option<&OsStr>) -> Option<String> {
     Some(value?.to_str()?.to_ascii_lowercase())
 }
 
+// The output of `dotnet` isn't cached: the version depends on the `global.json` files of
+// the current directory, and the SDKs can be installed without changing the binary.
 fn get_version_from_cli() -> Option<Version> {
-    let version_output = match Command::new("dotnet").arg("--version").output() {
-        Ok(output) => output,
-        Err(e) => {
-            log::warn!("Failed to execute `dotnet --version`. {}", e);
+    let version_output = match utils::exec_cmd("dotnet", &["--version"]) {
+        Some(output) => output,
+        None => {
+            log::warn!("Failed to execute `dotnet --version`.");
             return None;
         }
     };
-    let version = str::from_utf8(version_output.stdout.as_slice())
-        .ok()?
-        .trim();
+    let version = version_output.stdout.trim();
 
     let mut buffer = String::with_capacity(version.len() + 1);
     buffer.push('v');
@@ -220,28 +220,13 @@ fn get_version_from_cli() -> Option<Version> {
 }
 
 fn get_latest_sdk_from_cli() -> Option<Version> {
-    let mut cmd = Command::new("dotnet");
-    cmd.arg("--list-sdks")
-        .stdout(Stdio::piped())
-        .stderr(Stdio::null())
-        .stdin(Stdio::null());
-
-    let exit_code = match cmd.status() {
-        Ok(status) => status,
-        Err(e) => {
-            log::warn!("Failed to execute `dotnet --list-sdks`. {}", e);
-            return None;
-        }
-    };
-
-    if exit_code.success() {
-        let sdks_output = cmd.output().ok()?;
+    if let Some(sdks_output) = utils::exec_cmd("dotnet", &["--list-sdks"]) {
         fn parse_failed<T>() -> Option<T> {
             log::warn!("Unable to parse the output from `dotnet --list-sdks`.");
             None
         };
-        let latest_sdk = str::from_utf8(sdks_output.stdout.as_slice())
-            .ok()?
+        let latest_sdk = sdks_output
+            .stdout
             .lines()
             .map(str::trim)
             .filter(|l| !l.is_empty())
@@ -259,9 +244,9 @@ fn get_latest_sdk_from_cli() -> Option<Version> {
         }
     } else {
         // Older versions of the dotnet cli do not support the --list-sdks command
-        // So, if the status code indicates failure, fall back to `dotnet --version`
+        // So, if the command fails, fall back to `dotnet --version`
         log::warn!(
-            "Received a non-success exit code from `dotnet --list-sdks`. \
+            "Failed to execute `dotnet --list-sdks`. \
              Falling back to `dotnet --version`.",
         );
         get_version_from_cli()
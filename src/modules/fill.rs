@@ -0,0 +1,20 @@
+use super::{Context, Module};
+
+use crate::config::{RootModuleConfig, SegmentConfig};
+use crate::configs::fill::FillConfig;
+
+/// Creates a module which fills the rest of the line with its symbol.
+///
+/// The module only holds the symbol, which the prompt repeats once the width of the rest
+/// of the line is known.
+pub fn module<'a>(context: &'a Context) -> Option<Module<'a>> {
+    let mut module = context.new_module("fill");
+    let config: FillConfig = FillConfig::try_load(module.config);
+
+    module.set_style(config.style);
+    module.get_prefix().set_value("");
+    module.get_suffix().set_value("");
+    module.create_segment("symbol", &SegmentConfig::new(config.symbol));
+
+    Some(module)
+}
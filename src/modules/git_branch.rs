@@ -40,7 +40,7 @@ pub fn module<'a>(context: &'a Context) -> Option<Module<'a>> {
     };
 
     module.create_segment(
-        "name",
+        "branch",
         &config.branch_name.with_value(&truncated_and_symbol),
     );
 
@@ -175,6 +175,9 @@ fn create_segment_with_count<'a>(
     count_config: CountConfig,
 ) {
     if count > 0 {
+        // Counts are always available to format strings, even when not shown by default
+        module.set_variable(&format!("{}_count", name), count.to_string());
+
         module.create_segment(name, &config);
 
         if count_config.enabled {
@@ -31,8 +31,11 @@ pub fn module<'a>(context: &'a Context) -> Option<Module<'a>> {
     module.set_style(config.style);
     module.create_segment("symbol", &config.symbol);
 
-    let formatted_version =
-        format_go_version(&utils::exec_cmd("go", &["version"])?.stdout.as_str())?;
+    let formatted_version = format_go_version(
+        &utils::exec_cmd_cached("go", &["version"], &[])?
+            .stdout
+            .as_str(),
+    )?;
     module.create_segment("version", &config.version.with_value(&formatted_version));
 
     Some(module)
@@ -43,7 +43,7 @@ fn get_java_version() -> Option<String> {
         Err(_) => String::from("java"),
     };
 
-    let output = utils::exec_cmd(&java_command.as_str(), &["-Xinternalversion"])?;
+    let output = utils::exec_cmd_cached(java_command.as_str(), &["-Xinternalversion"], &[])?;
     Some(format!("{}{}", output.stdout, output.stderr))
 }
 
@@ -3,9 +3,11 @@ mod aws;
 mod character;
 mod cmd_duration;
 mod conda;
+mod custom;
 mod directory;
 mod dotnet;
 mod env_var;
+mod fill;
 mod git_branch;
 mod git_commit;
 mod git_state;
@@ -25,6 +27,7 @@ mod php;
 mod python;
 mod ruby;
 mod rust;
+mod status;
 mod terraform;
 mod time;
 mod username;
@@ -37,8 +40,14 @@ use crate::config::{RootModuleConfig, SegmentConfig};
 use crate::context::Context;
 use crate::module::Module;
 
+use std::convert::TryFrom;
+use std::time::{Duration, Instant};
+
 pub fn handle<'a>(module: &str, context: &'a Context) -> Option<Module<'a>> {
-    match module {
+    let start = Instant::now();
+    let timeout = command_timeout(module, context);
+
+    let module = crate::utils::with_command_timeout(timeout, || match module {
         // Keep these ordered alphabetically.
         // Default ordering is handled in configs/mod.rs
         "aws" => aws::module(context),
@@ -50,6 +59,7 @@ pub fn handle<'a>(module: &str, context: &'a Context) -> Option<Module<'a>> {
         "directory" => directory::module(context),
         "dotnet" => dotnet::module(context),
         "env_var" => env_var::module(context),
+        "fill" => fill::module(context),
         "git_branch" => git_branch::module(context),
         "git_commit" => git_commit::module(context),
         "git_state" => git_state::module(context),
@@ -69,14 +79,34 @@ pub fn handle<'a>(module: &str, context: &'a Context) -> Option<Module<'a>> {
         "python" => python::module(context),
         "ruby" => ruby::module(context),
         "rust" => rust::module(context),
+        "status" => status::module(context),
         "terraform" => terraform::module(context),
         "time" => time::module(context),
         "username" => username::module(context),
+        name if name.starts_with("custom.") => custom::module(name, context),
         _ => {
             eprintln!("Error: Unknown module {}. Use starship module --list to list out all supported modules.", module);
             None
         }
-    }
+    });
+
+    module.map(|mut module| {
+        module.set_duration(start.elapsed());
+        module
+    })
+}
+
+/// The timeout for external commands run by a module, which can be set per module and
+/// otherwise defaults to the `command_timeout` of the prompt
+fn command_timeout(module: &str, context: &Context) -> Duration {
+    let timeout = context
+        .config
+        .get_module_config(module)
+        .and_then(|config| config.get("command_timeout")?.as_integer())
+        .and_then(|timeout| u64::try_from(timeout).ok())
+        .unwrap_or_else(|| context.config.get_root_config().command_timeout);
+
+    Duration::from_millis(timeout)
 }
 
 pub fn description(module: &str) -> &'static str {
@@ -91,6 +121,7 @@ pub fn description(module: &str) -> &'static str {
         "directory" => "The current working directory",
         "dotnet" => "The relevant version of the .NET Core SDK for the current directory",
         "env_var" => "Displays the current value of a selected environment variable",
+        "fill" => "Fills the rest of the line, pushing the modules after it to the right",
         "git_branch" => "The active branch of the repo in your current directory",
         "git_commit" => "The active commit of the repo in your current directory",
         "git_state" => "The current git operation, and it's progress",
@@ -110,6 +141,7 @@ pub fn description(module: &str) -> &'static str {
         "python" => "The currently installed version of Python",
         "ruby" => "The currently installed version of Ruby",
         "rust" => "The currently installed version of Rust",
+        "status" => "The exit status of the last command, if it failed",
         "terraform" => "The currently selected terraform workspace and version",
         "time" => "The current local time",
         "username" => "The active user's username",
@@ -21,7 +21,7 @@ pub fn module<'a>(context: &'a Context) -> Option<Module<'a>> {
         return None;
     }
 
-    let node_version = utils::exec_cmd("node", &["--version"])?.stdout;
+    let node_version = utils::exec_cmd_cached("node", &["--version"], &[])?.stdout;
 
     let mut module = context.new_module("nodejs");
     let config: NodejsConfig = NodejsConfig::try_load(module.config);
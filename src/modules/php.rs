@@ -1,8 +1,7 @@
-use std::process::Command;
-
 use super::{Context, Module, RootModuleConfig, SegmentConfig};
 
 use crate::configs::php::PhpConfig;
+use crate::utils;
 
 /// Creates a module with the current PHP version
 ///
@@ -38,14 +37,15 @@ pub fn module<'a>(context: &'a Context) -> Option<Module<'a>> {
 }
 
 fn get_php_version() -> Option<String> {
-    match Command::new("php")
-        .arg("-r")
-        .arg("echo PHP_MAJOR_VERSION.'.'.PHP_MINOR_VERSION.'.'.PHP_RELEASE_VERSION;")
-        .output()
-    {
-        Ok(output) => Some(String::from_utf8(output.stdout).unwrap()),
-        Err(_) => None,
-    }
+    let output = utils::exec_cmd_cached(
+        "php",
+        &[
+            "-r",
+            "echo PHP_MAJOR_VERSION.'.'.PHP_MINOR_VERSION.'.'.PHP_RELEASE_VERSION;",
+        ],
+        &[],
+    )?;
+    Some(output.stdout)
 }
 
 fn format_php_version(php_version: &str) -> Option<String> {
@@ -60,7 +60,7 @@ pub fn module<'a>(context: &'a Context) -> Option<Module<'a>> {
 }
 
 fn get_python_version() -> Option<String> {
-    match utils::exec_cmd("python", &["--version"]) {
+    match utils::exec_cmd_cached("python", &["--version"], &["PYENV_VERSION"]) {
         Some(output) => {
             if output.stdout.is_empty() {
                 Some(output.stderr)
@@ -19,7 +19,7 @@ pub fn module<'a>(context: &'a Context) -> Option<Module<'a>> {
         return None;
     }
 
-    let ruby_version = utils::exec_cmd("ruby", &["-v"])?.stdout;
+    let ruby_version = utils::exec_cmd_cached("ruby", &["-v"], &["RBENV_VERSION"])?.stdout;
     let formatted_version = format_ruby_version(&ruby_version)?;
 
     let mut module = context.new_module("ruby");
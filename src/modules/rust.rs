@@ -6,6 +6,7 @@ use std::{env, fs};
 use super::{Context, Module, RootModuleConfig};
 
 use crate::configs::rust::RustConfig;
+use crate::utils;
 
 /// Creates a module with the current Rust version
 ///
@@ -73,10 +74,8 @@ fn env_rustup_toolchain() -> Option<String> {
 }
 
 fn execute_rustup_override_list(cwd: &Path) -> Option<String> {
-    let Output { stdout, .. } = Command::new("rustup")
-        .args(&["override", "list"])
-        .output()
-        .ok()?;
+    let Output { stdout, .. } =
+        utils::command_output(Command::new("rustup").args(&["override", "list"])).ok()?;
     let stdout = String::from_utf8(stdout).ok()?;
     extract_toolchain_from_rustup_override_list(&stdout, cwd)
 }
@@ -128,9 +127,7 @@ fn find_rust_toolchain_file(context: &Context) -> Option<String> {
 }
 
 fn execute_rustup_run_rustc_version(toolchain: &str) -> RustupRunRustcVersionOutcome {
-    Command::new("rustup")
-        .args(&["run", toolchain, "rustc", "--version"])
-        .output()
+    utils::command_output(Command::new("rustup").args(&["run", toolchain, "rustc", "--version"]))
         .map(extract_toolchain_from_rustup_run_rustc_version)
         .unwrap_or(RustupRunRustcVersionOutcome::RustupNotWorking)
 }
@@ -152,7 +149,7 @@ fn extract_toolchain_from_rustup_run_rustc_version(output: Output) -> RustupRunR
 }
 
 fn execute_rustc_version() -> Option<String> {
-    match Command::new("rustc").arg("--version").output() {
+    match utils::command_output(Command::new("rustc").arg("--version")) {
         Ok(output) => Some(String::from_utf8(output.stdout).unwrap()),
         Err(_) => None,
     }
@@ -0,0 +1,97 @@
+use super::{Context, Module, RootModuleConfig, SegmentConfig};
+
+use crate::configs::status::StatusConfig;
+
+/// Creates a module with the exit status of the last command
+///
+/// The module is only shown if the last command failed, or if any command of the last
+/// pipeline failed. For a pipeline, the status of each of its commands is shown
+/// (e.g. `0|1|0`). The status is followed by its meaning when it has a common one: the
+/// name of the signal which killed the command, or whether it was not found.
+pub fn module<'a>(context: &'a Context) -> Option<Module<'a>> {
+    let mut module = context.new_module("status");
+    let config: StatusConfig = StatusConfig::try_load(module.config);
+    if config.disabled {
+        return None;
+    }
+
+    let props = &context.properties;
+    let pipestatus = props
+        .get("pipestatus")
+        .map(|pipestatus| pipestatus.split_whitespace().collect::<Vec<&str>>())
+        .unwrap_or_default();
+    let status = props
+        .get("status_code")
+        .map(String::as_str)
+        .or_else(|| pipestatus.last().copied())?
+        .trim();
+
+    if status == "0" && pipestatus.iter().all(|status| *status == "0") {
+        return None;
+    }
+
+    module.set_style(config.style);
+    module.get_prefix().set_value("");
+
+    module.create_segment("symbol", &config.symbol);
+    // The status of a single command is the same as its pipeline's
+    if pipestatus.len() > 1 {
+        let pipestatus = pipestatus.join(config.pipestatus_separator);
+        module.create_segment("status", &SegmentConfig::new(&pipestatus));
+    } else {
+        module.create_segment("status", &SegmentConfig::new(status));
+    }
+
+    let meaning = status.parse::<i64>().ok().and_then(status_meaning);
+    if let (true, Some(meaning)) = (config.show_meaning, meaning) {
+        module.create_segment("meaning", &SegmentConfig::new(&format!(" {}", meaning)));
+    }
+
+    Some(module)
+}
+
+/// The meaning of an exit status which is set by the shell rather than by the command
+fn status_meaning(status: i64) -> Option<&'static str> {
+    match status {
+        126 => Some("not executable"),
+        127 => Some("not found"),
+        // A command killed by a signal exits with 128 plus the number of the signal
+        status if status > 128 => signal_name(status - 128),
+        _ => None,
+    }
+}
+
+/// The name of a signal, for the signals which have the same number on every platform
+fn signal_name(signal: i64) -> Option<&'static str> {
+    match signal {
+        1 => Some("SIGHUP"),
+        2 => Some("SIGINT"),
+        3 => Some("SIGQUIT"),
+        4 => Some("SIGILL"),
+        5 => Some("SIGTRAP"),
+        6 => Some("SIGABRT"),
+        8 => Some("SIGFPE"),
+        9 => Some("SIGKILL"),
+        11 => Some("SIGSEGV"),
+        13 => Some("SIGPIPE"),
+        14 => Some("SIGALRM"),
+        15 => Some("SIGTERM"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_meanings() {
+        assert_eq!(status_meaning(1), None);
+        assert_eq!(status_meaning(126), Some("not executable"));
+        assert_eq!(status_meaning(127), Some("not found"));
+        assert_eq!(status_meaning(128), None);
+        assert_eq!(status_meaning(130), Some("SIGINT"));
+        assert_eq!(status_meaning(139), Some("SIGSEGV"));
+        assert_eq!(status_meaning(138), None);
+    }
+}
@@ -29,8 +29,11 @@ pub fn module<'a>(context: &'a Context) -> Option<Module<'a>> {
     module.create_segment("symbol", &config.symbol);
 
     if config.show_version {
-        let terraform_version =
-            format_terraform_version(&utils::exec_cmd("terraform", &["version"])?.stdout.as_str())?;
+        let terraform_version = format_terraform_version(
+            &utils::exec_cmd_cached("terraform", &["version"], &[])?
+                .stdout
+                .as_str(),
+        )?;
         module.create_segment("version", &config.version.with_value(&terraform_version));
     }
 
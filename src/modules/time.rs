@@ -3,7 +3,7 @@ use chrono::{DateTime, FixedOffset, Local, Utc};
 use super::{Context, Module};
 
 use crate::config::{RootModuleConfig, SegmentConfig};
-use crate::configs::time::TimeConfig;
+use crate::configs::time::{self, TimeConfig};
 
 /// Outputs the current time
 pub fn module<'a>(context: &'a Context) -> Option<Module<'a>> {
@@ -15,8 +15,20 @@ pub fn module<'a>(context: &'a Context) -> Option<Module<'a>> {
         return None;
     };
 
+    // A strftime `format` is the time format of configs written before format strings
+    let legacy_format = module
+        .config
+        .and_then(|config| config.get("format")?.as_str())
+        .filter(|format| time::is_legacy_format(format));
+    if legacy_format.is_some() {
+        module.clear_format();
+    }
+
     let default_format = if config.use_12hr { "%r" } else { "%T" };
-    let time_format = config.format.unwrap_or(default_format);
+    let time_format = config
+        .time_format
+        .or(legacy_format)
+        .unwrap_or(default_format);
 
     log::trace!(
         "Timer module is enabled with format string: {}",
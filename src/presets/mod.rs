@@ -0,0 +1,118 @@
+use std::process;
+
+use crate::config::closest_match;
+
+/// A configuration bundled with starship, which `starship preset` prints
+pub struct Preset {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub config: &'static str,
+}
+
+pub const PRESETS: &[Preset] = &[
+    Preset {
+        name: "bracketed-segments",
+        description: "Every module in brackets, instead of after words like \"via\" or \"on\"",
+        config: include_str!("bracketed-segments.toml"),
+    },
+    Preset {
+        name: "dracula",
+        description: "The colors of the Dracula theme, named in a palette",
+        config: include_str!("dracula.toml"),
+    },
+    Preset {
+        name: "nerd-font-symbols",
+        description: "Nerd Font symbols for every module",
+        config: include_str!("nerd-font-symbols.toml"),
+    },
+    Preset {
+        name: "pastel-powerline",
+        description: "A powerline prompt with pastel backgrounds, using Nerd Font separators",
+        config: include_str!("pastel-powerline.toml"),
+    },
+    Preset {
+        name: "plain-text-symbols",
+        description: "Plain text symbols, for terminals without emoji or Nerd Fonts",
+        config: include_str!("plain-text-symbols.toml"),
+    },
+];
+
+/// Print the configuration of the preset named `name` to stdout, to be redirected to the
+/// configuration file. Without a name, the presets are listed instead.
+pub fn print_preset(name: Option<&str>) {
+    let name = match name {
+        Some(name) => name,
+        None => {
+            print_list();
+            return;
+        }
+    };
+
+    match PRESETS.iter().find(|preset| preset.name == name) {
+        Some(preset) => print!("{}", preset.config),
+        None => {
+            let names = PRESETS
+                .iter()
+                .map(|preset| preset.name)
+                .collect::<Vec<&str>>();
+            match closest_match(name, &names) {
+                Some(suggestion) => eprintln!(
+                    "Unknown preset \"{}\", did you mean \"{}\"?",
+                    name, suggestion
+                ),
+                None => eprintln!(
+                    "Unknown preset \"{}\", run `starship preset --list` to see the presets",
+                    name
+                ),
+            }
+            process::exit(1);
+        }
+    }
+}
+
+/// Print the name and description of each preset
+pub fn print_list() {
+    let name_width = PRESETS
+        .iter()
+        .map(|preset| preset.name.len())
+        .max()
+        .unwrap_or(0);
+    for preset in PRESETS {
+        println!(
+            "{:width$}  -  {}",
+            preset.name,
+            preset.description,
+            width = name_width
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config, config_check};
+
+    #[test]
+    fn presets_are_valid() {
+        for preset in PRESETS {
+            let config = toml::from_str(preset.config).unwrap();
+            let palette = config::get_palette(&config).unwrap_or_default();
+            let diagnostics = config_check::check_config(&config, &palette)
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<String>>();
+            assert_eq!(diagnostics, Vec::<String>::new(), "in {}", preset.name);
+        }
+    }
+
+    #[test]
+    fn presets_are_sorted() {
+        let names = PRESETS
+            .iter()
+            .map(|preset| preset.name)
+            .collect::<Vec<&str>>();
+        let mut sorted = names.clone();
+        sorted.sort();
+        assert_eq!(names, sorted);
+    }
+}
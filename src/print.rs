@@ -1,19 +1,52 @@
+use ansi_term::{ANSIString, ANSIStrings, Color, Style};
 use clap::ArgMatches;
 use rayon::prelude::*;
+use serde_json::json;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Write as FmtWrite;
 use std::io::{self, Write};
-use unicode_width::UnicodeWidthChar;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
+use crate::config::{parse_style_string, ModuleConfig};
+use crate::config_check;
+use crate::configs::PROMPT_ORDER;
 use crate::context::Context;
-use crate::module::Module;
-use crate::module::ALL_MODULES;
+use crate::formatter::{self, FormatElement, StyleElement};
+use crate::module::{ansi_strings_for_shell, Module, ALL_MODULES};
 use crate::modules;
+use crate::segment::Segment;
+
+/// The OSC 133 mark of the start of the prompt, for terminals to jump between prompts
+const PROMPT_START_MARK: &str = "\x1b]133;A\x07";
+
+/// The OSC 133 mark of the end of the prompt, where the command line starts
+const PROMPT_END_MARK: &str = "\x1b]133;B\x07";
 
 pub fn prompt(args: ArgMatches) {
+    let right = args.is_present("right");
+    let transient = args.is_present("transient");
+    let continuation = args.is_present("continuation");
+    let json = args.is_present("json");
+    let title = args.is_present("title");
     let context = Context::new(args);
     let stdout = io::stdout();
     let mut handle = stdout.lock();
-    write!(handle, "{}", get_prompt(context)).unwrap();
+    if right {
+        write!(handle, "{}", get_right_prompt(context)).unwrap();
+    } else if transient {
+        write!(handle, "{}", get_transient_prompt(context)).unwrap();
+    } else if continuation {
+        write!(handle, "{}", get_continuation_prompt(context)).unwrap();
+    } else if json {
+        writeln!(handle, "{}", get_json_prompt(context)).unwrap();
+    } else if title {
+        write!(handle, "{}", get_title(context)).unwrap();
+    } else {
+        config_check::warn_once_per_session(&context.config);
+        write!(handle, "{}", get_prompt(context)).unwrap();
+    }
 }
 
 pub fn get_prompt(context: Context) -> String {
@@ -26,25 +59,291 @@ pub fn get_prompt(context: Context) -> String {
     }
 
     buf.push_str("\x1b[J");
+    // fish sets the title from `fish_title` instead, see `get_title`
+    if config.title.enabled && !is_fish() {
+        let title = render_title(&context, config.title.format);
+        buf.push_str(&escape_for_shell(title_osc(&title)));
+    }
+    if config.shell_integration {
+        let marks = format!(
+            "{}{}",
+            working_directory_osc(&context.current_dir),
+            PROMPT_START_MARK
+        );
+        buf.push_str(&escape_for_shell(marks));
+    }
+    buf.push_str(&render_format(
+        &context,
+        config.format,
+        config.right_format,
+        false,
+    ));
+    if config.shell_integration {
+        buf.push_str(&escape_for_shell(PROMPT_END_MARK.to_string()));
+    }
 
-    let modules = compute_modules(&context);
+    buf
+}
 
-    let mut print_without_prefix = true;
-    let printable = modules.iter();
+pub fn get_right_prompt(context: Context) -> String {
+    let config = context.config.get_root_config();
+    let prompt = render_format(&context, config.right_format, config.format, true);
 
-    for module in printable {
-        // Skip printing the prefix of a module after the line_break
-        if print_without_prefix {
-            let module_without_prefix = module.to_string_without_prefix();
-            write!(buf, "{}", module_without_prefix).unwrap()
-        } else {
-            write!(buf, "{}", module).unwrap();
+    let shell = std::env::var("STARSHIP_SHELL").unwrap_or_default();
+    if prompt.is_empty() || shell != "powershell" {
+        return prompt;
+    }
+
+    // PowerShell has no right prompt of its own, so the cursor is moved to the right edge
+    // of the terminal and restored once the right prompt has been printed.
+    match terminal_width(&context) {
+        Some(width) => {
+            let column = width.saturating_sub(display_width(&prompt)) + 1;
+            format!("\x1b[s\x1b[{}G{}\x1b[u", column, prompt)
+        }
+        None => {
+            log::debug!("Unable to find the terminal width, skipping the right prompt");
+            String::new()
         }
+    }
+}
+
+/// Render the minimal prompt which replaces a prompt once its command has been accepted
+pub fn get_transient_prompt(context: Context) -> String {
+    let config = context.config.get_root_config();
+    render_format(&context, config.transient_format, "", false)
+}
+
+/// Render the prompt shown while a command spans several lines
+pub fn get_continuation_prompt(context: Context) -> String {
+    let config = context.config.get_root_config();
+    render_format(&context, config.continuation_prompt, "", false)
+}
 
-        print_without_prefix = module.get_name() == "line_break"
+/// Render the sequence which sets the terminal's title, with `title.command_format` while
+/// a command runs and with `title.format` otherwise
+pub fn get_title(context: Context) -> String {
+    let config = context.config.get_root_config().title;
+    if !config.enabled {
+        return String::new();
     }
 
-    buf
+    let format = if context.properties.contains_key("command") {
+        config.command_format
+    } else {
+        config.format
+    };
+    let title = render_title(&context, format);
+    // fish wraps the output of `fish_title` in the sequence itself
+    if is_fish() {
+        title
+    } else {
+        title_osc(&title)
+    }
+}
+
+fn is_fish() -> bool {
+    std::env::var("STARSHIP_SHELL").as_deref() == Ok("fish")
+}
+
+/// Serialize every module shown in the prompt, in the order they appear in the left and
+/// then the right prompt
+pub fn get_json_prompt(context: Context) -> String {
+    let modules = compute_modules(&context)
+        .iter()
+        .map(Module::to_json)
+        .collect::<Vec<serde_json::Value>>();
+
+    json!({ "modules": modules }).to_string()
+}
+
+/// Render a root format string, computing the modules it contains. `$all` skips the
+/// modules which are named in either format.
+fn render_format(context: &Context, format: &str, other_format: &str, is_right: bool) -> String {
+    let powerline = context.config.get_root_config().powerline;
+    let entries = parse_format(format, other_format, &context.custom_modules);
+    let mut modules = handle_modules(context, &entries);
+    if powerline.enabled {
+        for (name, module) in modules.iter_mut() {
+            // A line break ends the blocks of a line rather than being one itself
+            if *name != "line_break" {
+                let background = module
+                    .config
+                    .and_then(|config| Color::from_config(config.get("background")?))
+                    .or(powerline.background);
+                module.set_background(background);
+            }
+        }
+    }
+    let visible_entries = visible_entries(&entries, &modules);
+
+    let separator = if is_right {
+        powerline.right_separator
+    } else {
+        powerline.separator
+    };
+    // The background of the entry before the current one, which the powerline separator
+    // continues from. Literal text is shown on the terminal's background.
+    let mut background = None;
+    let mut pieces = Vec::new();
+    for (i, entry) in visible_entries.iter().enumerate() {
+        if powerline.enabled {
+            let next_background = match &entry.item {
+                PromptItem::Module(name) => modules[name].get_background(),
+                PromptItem::Text(_) => None,
+            };
+            pieces.push(RenderedPiece::Text(powerline_separator(
+                separator,
+                background,
+                next_background,
+                is_right,
+            )));
+            background = next_background;
+        }
+
+        match &entry.item {
+            PromptItem::Text(text) => {
+                let ansi_strings = ansi_strings_for_shell(vec![text.ansi_string()]);
+                pieces.push(RenderedPiece::Text(ANSIStrings(&ansi_strings).to_string()));
+            }
+            PromptItem::Module("fill") => pieces.push(RenderedPiece::Fill(&modules["fill"])),
+            PromptItem::Module(name) => {
+                // A module's prefix separates it from the module before it, so it is skipped at
+                // the start of a line and after literal text. Likewise, its suffix is skipped
+                // when literal text follows.
+                let prefix = match i.checked_sub(1).map(|i| &visible_entries[i].item) {
+                    Some(PromptItem::Module(previous)) => *previous != "line_break",
+                    _ => false,
+                };
+                let suffix = !matches!(
+                    visible_entries.get(i + 1).map(|entry| &entry.item),
+                    Some(PromptItem::Text(_))
+                );
+
+                pieces.push(RenderedPiece::Text(
+                    modules[name].to_string_with_affixes(prefix, suffix),
+                ));
+            }
+        }
+    }
+    if powerline.enabled {
+        pieces.push(RenderedPiece::Text(powerline_separator(
+            separator, background, None, is_right,
+        )));
+    }
+
+    // The terminal width is only looked up if it is needed
+    let has_fill = pieces
+        .iter()
+        .any(|piece| matches!(piece, RenderedPiece::Fill(_)));
+    let width = if has_fill {
+        terminal_width(context)
+    } else {
+        None
+    };
+    expand_fills(pieces, width)
+}
+
+/// A part of the rendered prompt
+enum RenderedPiece<'a, 'b> {
+    Text(String),
+    /// A `fill` module, which is expanded once the width of the rest of its line is known
+    Fill(&'b Module<'a>),
+}
+
+/// Join the pieces of a rendered prompt, expanding the fill modules of each line so that
+/// the line takes up `width` columns. Several fill modules on a line share the space
+/// evenly. Without a width, the fill modules are left out.
+fn expand_fills(pieces: Vec<RenderedPiece>, width: Option<usize>) -> String {
+    let mut lines = vec![Vec::new()];
+    for piece in pieces {
+        match piece {
+            RenderedPiece::Text(text) => {
+                let mut parts = text.split('\n');
+                if let Some(first) = parts.next() {
+                    lines
+                        .last_mut()
+                        .unwrap()
+                        .push(RenderedPiece::Text(first.to_owned()));
+                }
+                for part in parts {
+                    lines.push(vec![RenderedPiece::Text(part.to_owned())]);
+                }
+            }
+            fill => lines.last_mut().unwrap().push(fill),
+        }
+    }
+
+    lines
+        .into_iter()
+        .map(|line| {
+            let text_width = line
+                .iter()
+                .map(|piece| match piece {
+                    RenderedPiece::Text(text) => display_width(text),
+                    RenderedPiece::Fill(_) => 0,
+                })
+                .sum::<usize>();
+            let fill_count = line
+                .iter()
+                .filter(|piece| matches!(piece, RenderedPiece::Fill(_)))
+                .count();
+            let free_width = width.unwrap_or(0).saturating_sub(text_width);
+
+            let mut fill_index = 0;
+            line.into_iter()
+                .map(|piece| match piece {
+                    RenderedPiece::Text(text) => text,
+                    RenderedPiece::Fill(module) => {
+                        // The columns which can't be shared evenly go to the first fills
+                        let extra = usize::from(fill_index < free_width % fill_count);
+                        fill_index += 1;
+                        module.to_string_filled(free_width / fill_count + extra)
+                    }
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// The width of the terminal, as given by the shell or otherwise looked up
+fn terminal_width(context: &Context) -> Option<usize> {
+    context
+        .properties
+        .get("terminal_width")
+        .and_then(|width| width.parse::<usize>().ok())
+        .or_else(|| term_size::dimensions().map(|(w, _)| w))
+}
+
+/// Render the separator between two blocks of a powerline prompt, which are shown on the
+/// given backgrounds. `None` is the terminal's background, e.g. at the start of a line.
+/// On the left, the separator points from the previous block into the next one, so it is
+/// drawn in the previous block's color. On the right, it points the other way.
+fn powerline_separator(
+    separator: &str,
+    previous: Option<Color>,
+    next: Option<Color>,
+    is_right: bool,
+) -> String {
+    let (block, other) = if is_right {
+        (next, previous)
+    } else {
+        (previous, next)
+    };
+    let block = match block {
+        Some(block) => block,
+        None => return String::new(),
+    };
+
+    let mut segment = Segment::new("_separator");
+    segment.set_value(separator);
+    segment.set_style(Style {
+        background: other,
+        ..block.normal()
+    });
+    let ansi_strings = ansi_strings_for_shell(vec![segment.ansi_string()]);
+    ANSIStrings(&ansi_strings).to_string()
 }
 
 pub fn module(module_name: &str, args: ArgMatches) {
@@ -77,7 +376,7 @@ pub fn explain(args: ArgMatches) {
             let value = module.get_segments().join("");
             ModuleInfo {
                 value: ansi_term::ANSIStrings(&ansi_strings[1..ansi_strings.len() - 1]).to_string(),
-                value_len: value.chars().count() + count_wide_chars(&value),
+                value_len: display_width(&value),
                 desc: module.get_description().to_owned(),
             }
         })
@@ -124,32 +423,602 @@ pub fn explain(args: ArgMatches) {
             );
         };
     }
+
+    let palette = config_check::merged_palette(&context.config);
+    let diagnostics = context
+        .config
+        .config
+        .as_ref()
+        .map(|config| config_check::check_config(config, &palette))
+        .unwrap_or_default();
+    if !diagnostics.is_empty() {
+        println!("\n Your configuration has some problems:");
+        for diagnostic in diagnostics {
+            println!(" - {}", diagnostic);
+        }
+    }
 }
 
-fn compute_modules<'a>(context: &'a Context) -> Vec<Module<'a>> {
-    let mut prompt_order: Vec<&str> = Vec::new();
+/// Modules taking at least this long are highlighted in `starship timings`
+const SLOW_MODULE_THRESHOLD: Duration = Duration::from_millis(10);
 
-    // Write out a custom prompt order
-    for module in context.config.get_root_config().prompt_order {
-        if ALL_MODULES.contains(&module) {
-            prompt_order.push(module);
-        } else {
-            log::debug!(
-                "Expected prompt_order to contain value from {:?}. Instead received {}",
-                ALL_MODULES,
-                module,
-            );
+/// The time taken to compute a single module
+struct ModuleTiming {
+    name: String,
+    duration: Duration,
+    value: String,
+}
+
+pub fn timings(args: ArgMatches) {
+    let context = Context::new(args);
+    let config = context.config.get_root_config();
+
+    // The directory is only scanned once, so it is timed separately rather than being
+    // counted towards the first module which looks at its files
+    let start = Instant::now();
+    let _ = context.get_dir_files();
+    let scan_duration = start.elapsed();
+
+    let mut entries = parse_format(config.format, config.right_format, &context.custom_modules);
+    entries.extend(parse_format(
+        config.right_format,
+        config.format,
+        &context.custom_modules,
+    ));
+    let mut module_names = Vec::new();
+    for entry in &entries {
+        if let PromptItem::Module(name) = entry.item {
+            if !module_names.contains(&name) && context.is_module_shown(name) {
+                module_names.push(name);
+            }
+        }
+    }
+
+    // Modules are computed one at a time, so that each timing includes the external
+    // commands run by that module only
+    let timings = module_names
+        .into_iter()
+        .map(|name| {
+            let start = Instant::now();
+            let module = modules::handle(name, &context);
+            ModuleTiming {
+                name: name.to_string(),
+                duration: start.elapsed(),
+                value: module
+                    .map(|module| module.get_segments().join(""))
+                    .unwrap_or_default(),
+            }
+        })
+        .collect::<Vec<ModuleTiming>>();
+
+    let total = timings
+        .iter()
+        .map(|timing| timing.duration)
+        .sum::<Duration>()
+        + scan_duration;
+
+    println!("\n Here are the timings of the modules in your prompt:");
+    for line in timing_lines(timings) {
+        println!(" {}", line);
+    }
+    println!(
+        "\n Scanning the current directory took {}, and the prompt took {} in total.",
+        format_duration(scan_duration),
+        format_duration(total)
+    );
+}
+
+/// Format module timings as a table, slowest first, highlighting the slow modules
+fn timing_lines(mut timings: Vec<ModuleTiming>) -> Vec<String> {
+    timings.sort_by_key(|timing| std::cmp::Reverse(timing.duration));
+
+    let name_width = timings
+        .iter()
+        .map(|timing| timing.name.len())
+        .max()
+        .unwrap_or(0);
+    let durations = timings
+        .iter()
+        .map(|timing| format_duration(timing.duration))
+        .collect::<Vec<String>>();
+    let duration_width = durations.iter().map(String::len).max().unwrap_or(0);
+
+    timings
+        .iter()
+        .zip(durations)
+        .map(|(timing, duration)| {
+            let duration = format!("{:>width$}", duration, width = duration_width);
+            let duration = if timing.duration >= SLOW_MODULE_THRESHOLD {
+                Color::Red.bold().paint(duration).to_string()
+            } else {
+                duration
+            };
+            format!(
+                "{:name_width$}  -  {}  -  {:?}",
+                timing.name,
+                duration,
+                timing.value,
+                name_width = name_width
+            )
+        })
+        .collect()
+}
+
+fn format_duration(duration: Duration) -> String {
+    format!("{:.2}ms", duration.as_secs_f64() * 1000.0)
+}
+
+/// An element of the prompt, once the root format string has been parsed
+enum PromptItem<'a> {
+    Text(Segment),
+    Module(&'a str),
+}
+
+/// A prompt item, along with the conditional groups it is nested in
+struct PromptEntry<'a> {
+    item: PromptItem<'a>,
+    conditionals: Vec<usize>,
+}
+
+/// Parse the root format string into a flat list of literal text and module names,
+/// expanding `$all` to every module in the default prompt order which isn't used elsewhere
+/// in the format, or in the other side of the prompt. Likewise, `$custom` expands to every
+/// custom module which isn't used elsewhere.
+fn parse_format<'a>(
+    format: &'a str,
+    other_format: &str,
+    custom_modules: &'a [String],
+) -> Vec<PromptEntry<'a>> {
+    let elements = formatter::parse(format).unwrap_or_else(|error| {
+        log::warn!("Unable to parse format string {:?}: {}", format, error);
+        vec![FormatElement::Variable("all")]
+    });
+    let other_elements = formatter::parse(other_format).unwrap_or_default();
+
+    let named_modules = elements
+        .iter()
+        .chain(other_elements.iter())
+        .flat_map(FormatElement::variables)
+        .filter(|name| *name != "all")
+        .collect::<Vec<&str>>();
+
+    let custom_modules = custom_modules
+        .iter()
+        .map(String::as_str)
+        .filter(|module| !named_modules.contains(module))
+        .collect::<Vec<&str>>();
+
+    let mut entries = Vec::new();
+    let mut conditional_count = 0;
+    flatten_elements(
+        elements,
+        None,
+        &[],
+        &mut conditional_count,
+        &named_modules,
+        &custom_modules,
+        &mut entries,
+    );
+    entries
+}
+
+fn flatten_elements<'a>(
+    elements: Vec<FormatElement<'a>>,
+    style: Option<Style>,
+    conditionals: &[usize],
+    conditional_count: &mut usize,
+    named_modules: &[&str],
+    custom_modules: &[&'a str],
+    entries: &mut Vec<PromptEntry<'a>>,
+) {
+    let entry = |item| PromptEntry {
+        item,
+        conditionals: conditionals.to_vec(),
+    };
+
+    for element in elements {
+        match element {
+            FormatElement::Text(text) => {
+                let mut segment = Segment::new("_text");
+                segment.set_value(text);
+                if let Some(style) = style {
+                    segment.set_style(style);
+                }
+                entries.push(entry(PromptItem::Text(segment)));
+            }
+            FormatElement::Variable("all") => {
+                let modules = PROMPT_ORDER
+                    .iter()
+                    .filter(|module| !named_modules.contains(module));
+                for module in modules {
+                    if *module == "custom" {
+                        custom_modules
+                            .iter()
+                            .for_each(|module| entries.push(entry(PromptItem::Module(module))));
+                    } else {
+                        entries.push(entry(PromptItem::Module(module)));
+                    }
+                }
+            }
+            FormatElement::Variable("custom") => custom_modules
+                .iter()
+                .for_each(|module| entries.push(entry(PromptItem::Module(module)))),
+            FormatElement::Variable(name) => {
+                if ALL_MODULES.contains(&name) || name.starts_with("custom.") {
+                    entries.push(entry(PromptItem::Module(name)));
+                } else {
+                    log::debug!(
+                        "Expected format to contain modules from {:?}. Instead received {}",
+                        ALL_MODULES,
+                        name,
+                    );
+                }
+            }
+            FormatElement::TextGroup(group) => {
+                // The style of a text group only applies to its literal text, modules
+                // keep their own style.
+                let group_style = match group.style {
+                    StyleElement::Text(style_string) => parse_style_string(style_string),
+                    StyleElement::Variable(name) => {
+                        log::warn!("Style variables can't be used in the root format: {}", name);
+                        None
+                    }
+                };
+                flatten_elements(
+                    group.format,
+                    group_style.or(style),
+                    conditionals,
+                    conditional_count,
+                    named_modules,
+                    custom_modules,
+                    entries,
+                );
+            }
+            FormatElement::Conditional(format) => {
+                let mut conditionals = conditionals.to_vec();
+                conditionals.push(*conditional_count);
+                *conditional_count += 1;
+                flatten_elements(
+                    format,
+                    style,
+                    &conditionals,
+                    conditional_count,
+                    named_modules,
+                    custom_modules,
+                    entries,
+                );
+            }
         }
     }
+}
+
+/// Filter out the entries which produce no output. A conditional group is only shown if
+/// any of the modules inside it is shown.
+fn visible_entries<'a, 'b>(
+    entries: &'b [PromptEntry<'a>],
+    modules: &HashMap<&str, Module>,
+) -> Vec<&'b PromptEntry<'a>> {
+    let shown_conditionals = entries
+        .iter()
+        .filter(|entry| match entry.item {
+            PromptItem::Module(name) => modules.contains_key(name),
+            PromptItem::Text(_) => false,
+        })
+        .flat_map(|entry| entry.conditionals.iter())
+        .collect::<HashSet<&usize>>();
+
+    entries
+        .iter()
+        .filter(|entry| {
+            entry
+                .conditionals
+                .iter()
+                .all(|conditional| shown_conditionals.contains(conditional))
+        })
+        .filter(|entry| match &entry.item {
+            PromptItem::Text(text) => !text.get_value().is_empty(),
+            PromptItem::Module(name) => modules.contains_key(name),
+        })
+        .collect()
+}
 
-    prompt_order
+/// Compute every module used in the prompt in parallel, keyed by module name.
+/// Modules which are disabled, hidden by their conditions or return `None` are left out.
+fn handle_modules<'a>(
+    context: &'a Context,
+    entries: &[PromptEntry<'a>],
+) -> HashMap<&'a str, Module<'a>> {
+    let mut module_names = entries
+        .iter()
+        .filter_map(|entry| match entry.item {
+            PromptItem::Module(name) => Some(name),
+            PromptItem::Text(_) => None,
+        })
+        .collect::<Vec<&str>>();
+    module_names.sort();
+    module_names.dedup();
+
+    module_names
         .par_iter()
-        .filter(|module| !context.is_module_disabled_in_config(module))
-        .map(|module| modules::handle(module, &context)) // Compute modules
-        .flatten() // Remove segments set to `None`
+        .filter(|module| context.is_module_shown(module))
+        .filter_map(|module| Some((*module, modules::handle(module, context)?))) // Compute modules
+        .collect::<HashMap<&str, Module<'a>>>()
+}
+
+/// Compute the modules of the prompt, in the order they appear in the left and then
+/// the right prompt
+fn compute_modules<'a>(context: &'a Context) -> Vec<Module<'a>> {
+    let config = context.config.get_root_config();
+    let mut entries = parse_format(config.format, config.right_format, &context.custom_modules);
+    entries.extend(parse_format(
+        config.right_format,
+        config.format,
+        &context.custom_modules,
+    ));
+    let mut modules = handle_modules(context, &entries);
+
+    entries
+        .iter()
+        .filter_map(|entry| match entry.item {
+            PromptItem::Module(name) => modules.remove(name),
+            PromptItem::Text(_) => None,
+        })
         .collect::<Vec<Module<'a>>>()
 }
 
+/// Compute the number of columns a string takes up once printed, skipping ANSI
+/// escape sequences
+fn display_width(value: &str) -> usize {
+    strip_escapes(value).width()
+}
+
+/// Render a title format as plain text. A module is shown as the text of its segments,
+/// and `$command` as the command which is about to run.
+fn render_title(context: &Context, format: &str) -> String {
+    let elements = formatter::parse(format).unwrap_or_else(|error| {
+        log::warn!("Unable to parse title format {:?}: {}", format, error);
+        Vec::new()
+    });
+    let (title, _) = render_title_elements(context, &elements);
+
+    // A title is a single line, so a command spanning several lines is joined
+    let title = title
+        .chars()
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .collect::<String>();
+    title.trim().to_string()
+}
+
+/// Render the elements of a title format, along with whether any of their variables has
+/// a value. As in the prompt, a conditional group is only shown if one of them has.
+fn render_title_elements(context: &Context, elements: &[FormatElement]) -> (String, bool) {
+    let mut title = String::new();
+    let mut has_value = false;
+    for element in elements {
+        let value = match element {
+            FormatElement::Text(text) => {
+                title.push_str(text);
+                continue;
+            }
+            FormatElement::Variable("command") => context.properties.get("command").cloned(),
+            FormatElement::Variable(name) => title_module(context, name),
+            FormatElement::TextGroup(group) => {
+                let (text, group_has_value) = render_title_elements(context, &group.format);
+                title.push_str(&text);
+                has_value |= group_has_value;
+                continue;
+            }
+            FormatElement::Conditional(format) => {
+                let (text, group_has_value) = render_title_elements(context, format);
+                if group_has_value {
+                    title.push_str(&text);
+                    has_value = true;
+                }
+                continue;
+            }
+        };
+
+        if let Some(value) = value.filter(|value| !value.is_empty()) {
+            title.push_str(&value);
+            has_value = true;
+        }
+    }
+    (title, has_value)
+}
+
+/// The text of a module in the title, without its prefix and suffix
+fn title_module(context: &Context, name: &str) -> Option<String> {
+    if !ALL_MODULES.contains(&name) && !name.starts_with("custom.") {
+        log::debug!("Unknown module in the title format: {}", name);
+        return None;
+    }
+    if !context.is_module_shown(name) {
+        return None;
+    }
+
+    let module = modules::handle(name, context)?;
+    Some(module.get_segments().concat().trim().to_string())
+}
+
+/// The OSC 0 sequence which sets the title of the terminal's window and tab. It ends with
+/// BEL, as the title is also part of the prompt, see `working_directory_osc`.
+fn title_osc(title: &str) -> String {
+    format!("\x1b]0;{}\x07", title)
+}
+
+/// Wrap the escape sequences of a string so that the shell knows they take no space
+fn escape_for_shell(value: String) -> String {
+    let escaped = ansi_strings_for_shell(vec![ANSIString::from(value)]);
+    ANSIStrings(&escaped).to_string()
+}
+
+/// The OSC 7 sequence telling the terminal the working directory, so that it can open new
+/// tabs in the same directory. Like the other sequences in the prompt, it ends with BEL
+/// rather than `ESC \`, whose backslash bash would read as escaping the `\]` after it.
+fn working_directory_osc(path: &Path) -> String {
+    let host = gethostname::gethostname();
+    let mut url = file_url(&host.to_string_lossy(), path);
+    if std::env::var("STARSHIP_SHELL").as_deref() == Ok("zsh") {
+        // % is an escape in zsh, see PROMPT in `man zshmisc`
+        url = url.replace('%', "%%");
+    }
+    format!("\x1b]7;{}\x07", url)
+}
+
+/// Build the `file://` URL of a path, percent-encoding the bytes which can't be in a URL
+fn file_url(host: &str, path: &Path) -> String {
+    let path = path.to_string_lossy();
+    let mut url = format!("file://{}", host);
+    // A Windows path such as `C:\Users` starts with the drive rather than a separator
+    if !path.starts_with('/') {
+        url.push('/');
+    }
+    for byte in path.bytes() {
+        match byte {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' | b':' => {
+                url.push(char::from(byte))
+            }
+            b'\\' => url.push('/'),
+            _ => write!(url, "%{:02X}", byte).unwrap(),
+        }
+    }
+    url
+}
+
+/// Remove the ANSI escape sequences from a string, along with the markers which wrap them
+/// for bash (`\[`, `\]`) and zsh (`%{`, `%}`)
+fn strip_escapes(value: &str) -> String {
+    let mut text = String::new();
+    let mut chars = value.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            // A control sequence (e.g. a style) ends with a character from `@` to `~`
+            ('\u{1b}', Some('[')) => {
+                chars.next();
+                chars.by_ref().find(|c| ('@'..='~').contains(c));
+            }
+            // An operating system command ends with BEL or with `ESC \`
+            ('\u{1b}', Some(']')) => {
+                while let Some(c) = chars.next() {
+                    if c == '\u{7}' || (c == '\u{1b}' && chars.next_if_eq(&'\\').is_some()) {
+                        break;
+                    }
+                }
+            }
+            ('\\', Some('[')) | ('\\', Some(']')) | ('%', Some('{')) | ('%', Some('}')) => {
+                chars.next();
+            }
+            _ => text.push(c),
+        }
+    }
+    text
+}
+
 fn count_wide_chars(value: &str) -> usize {
     value.chars().filter(|c| c.width().unwrap_or(0) > 1).count()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SegmentConfig;
+
+    #[test]
+    fn display_width_skips_escape_sequences() {
+        let value = format!("{} {}", Color::Red.bold().paint("took 3s"), "🕙");
+        assert_eq!(display_width(&value), 10);
+    }
+
+    #[test]
+    fn file_urls_are_percent_encoded() {
+        assert_eq!(
+            file_url("host", Path::new("/home/user/my project")),
+            "file://host/home/user/my%20project"
+        );
+        assert_eq!(
+            file_url("host", Path::new("/tmp/100%/ü")),
+            "file://host/tmp/100%25/%C3%BC"
+        );
+    }
+
+    #[test]
+    fn display_width_skips_shell_markers() {
+        assert_eq!(display_width("\\[\u{1b}[1;32m\\]❯\\[\u{1b}[0m\\] "), 2);
+        assert_eq!(display_width("%{\u{1b}[31m%}~%{\u{1b}[0m%}"), 1);
+        assert_eq!(
+            display_width("\u{1b}]7;file:///tmp\u{7}a\u{1b}]2;title\u{1b}\\b"),
+            2
+        );
+    }
+
+    #[test]
+    fn fills_share_the_free_width() {
+        let mut fill = Module::new("fill", "", None);
+        fill.create_segment("symbol", &SegmentConfig::new("."));
+        let text = |text: &str| RenderedPiece::Text(text.to_owned());
+
+        let pieces = vec![
+            text("ab"),
+            RenderedPiece::Fill(&fill),
+            text("c"),
+            RenderedPiece::Fill(&fill),
+            text(&format!("{}\nde", Color::Red.paint("f"))),
+            RenderedPiece::Fill(&fill),
+        ];
+        let expected = format!("ab...c..{}\nde.......", Color::Red.paint("f"));
+        assert_eq!(expand_fills(pieces, Some(9)), expected);
+
+        let pieces = vec![text("ab"), RenderedPiece::Fill(&fill), text("c")];
+        assert_eq!(expand_fills(pieces, Some(2)), "abc");
+        let pieces = vec![text("ab"), RenderedPiece::Fill(&fill), text("c")];
+        assert_eq!(expand_fills(pieces, None), "abc");
+    }
+
+    #[test]
+    fn powerline_separators_continue_blocks() {
+        let (blue, red) = (Some(Color::Blue), Some(Color::Red));
+
+        assert_eq!(
+            powerline_separator(">", blue, red, false),
+            Color::Blue.on(Color::Red).paint(">").to_string()
+        );
+        assert_eq!(
+            powerline_separator(">", blue, None, false),
+            Color::Blue.paint(">").to_string()
+        );
+        assert_eq!(powerline_separator(">", None, red, false), "");
+        assert_eq!(
+            powerline_separator("<", blue, red, true),
+            Color::Red.on(Color::Blue).paint("<").to_string()
+        );
+        assert_eq!(
+            powerline_separator("<", None, red, true),
+            Color::Red.paint("<").to_string()
+        );
+        assert_eq!(powerline_separator("<", blue, None, true), "");
+    }
+
+    #[test]
+    fn timing_lines_are_sorted_and_highlighted() {
+        let timing = |name: &str, millis, value: &str| ModuleTiming {
+            name: name.to_string(),
+            duration: Duration::from_millis(millis),
+            value: value.to_string(),
+        };
+        let timings = vec![
+            timing("directory", 1, "~"),
+            timing("git_status", 25, "[!]"),
+            timing("line_break", 0, "\n"),
+        ];
+
+        let expected = vec![
+            format!(
+                "git_status  -  {}  -  \"[!]\"",
+                Color::Red.bold().paint("25.00ms")
+            ),
+            "directory   -   1.00ms  -  \"~\"".to_string(),
+            "line_break  -   0.00ms  -  \"\\n\"".to_string(),
+        ];
+        assert_eq!(timing_lines(timings), expected);
+    }
+}
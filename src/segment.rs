@@ -1,12 +1,14 @@
+use crate::color;
 use ansi_term::{ANSIString, Style};
 use std::fmt;
 
 /// A segment is a single configurable element in a module. This will usually
 /// contain a data point to provide context for the prompt's user
 /// (e.g. The version that software is running).
+#[derive(Clone)]
 pub struct Segment {
     /// The segment's name, to be used in configuration and logging.
-    _name: String,
+    name: String,
 
     /// The segment's style. If None, will inherit the style of the module containing it.
     style: Option<Style>,
@@ -19,7 +21,7 @@ impl Segment {
     /// Creates a new segment with default fields.
     pub fn new(name: &str) -> Self {
         Self {
-            _name: name.to_string(),
+            name: name.to_string(),
             style: None,
             value: "".to_string(),
         }
@@ -45,6 +47,16 @@ impl Segment {
         self
     }
 
+    /// Gets the name of the segment.
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    /// Gets the style of the segment, if it has one of its own.
+    pub fn get_style(&self) -> Option<Style> {
+        self.style
+    }
+
     /// Gets the value of the segment.
     pub fn get_value(&self) -> &str {
         &self.value
@@ -53,11 +65,19 @@ impl Segment {
     // Returns the ANSIString of the segment value, not including its prefix and suffix
     pub fn ansi_string(&self) -> ANSIString {
         match self.style {
-            Some(style) => style.paint(&self.value),
+            Some(style) => color::adapt(style).paint(&self.value),
             None => ANSIString::from(&self.value),
         }
     }
 
+    /// Returns the ANSIString of the segment value, taking ownership of the value
+    pub fn into_ansi_string(self) -> ANSIString<'static> {
+        match self.style {
+            Some(style) => color::adapt(style).paint(self.value),
+            None => ANSIString::from(self.value),
+        }
+    }
+
     /// Determines if the segment contains a value.
     pub fn is_empty(&self) -> bool {
         self.value.trim().is_empty()
@@ -1,7 +1,13 @@
-use std::fs::File;
-use std::io::{Read, Result};
-use std::path::Path;
-use std::process::Command;
+use std::cell::Cell;
+use std::fs::{self, File};
+use std::io::{self, Read, Result};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::cache;
 
 /// Return the string contents of a file
 pub fn read_file<P: AsRef<Path>>(file_name: P) -> Result<String> {
@@ -12,6 +18,62 @@ pub fn read_file<P: AsRef<Path>>(file_name: P) -> Result<String> {
     Ok(data)
 }
 
+/// Convert a leading `~` in a path from the configuration to the home directory
+pub fn expand_home(path: &str) -> PathBuf {
+    match path.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => dirs::home_dir()
+            .unwrap_or_default()
+            .join(rest.trim_start_matches('/')),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Check whether `dir` is one of `dirs`, or inside one of them
+pub fn is_in_directories(dir: &Path, dirs: &[PathBuf]) -> bool {
+    // Compare canonical paths too, so that symlinks to one of the directories match
+    let canonical_dir = fs::canonicalize(dir).ok();
+    dirs.iter().any(|parent| {
+        dir.starts_with(parent)
+            || match (&canonical_dir, fs::canonicalize(parent)) {
+                (Some(canonical_dir), Ok(parent)) => canonical_dir.starts_with(parent),
+                _ => false,
+            }
+    })
+}
+
+/// Match `text` against a pattern in which `*` matches any run of characters and `?`
+/// matches a single character
+pub fn matches_wildcard(pattern: &str, text: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<char>>();
+    let text = text.chars().collect::<Vec<char>>();
+
+    // The positions to go back to when a mismatch follows the last `*`
+    let mut backtrack = None;
+    let (mut p, mut t) = (0, 0);
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(c) if *c == '?' || *c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star, star_t)) => {
+                    backtrack = Some((star, star_t + 1));
+                    p = star + 1;
+                    t = star_t + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
 #[derive(Debug)]
 pub struct CommandOutput {
     pub stdout: String,
@@ -24,10 +86,93 @@ impl PartialEq for CommandOutput {
     }
 }
 
+thread_local! {
+    /// How long external commands may run before they are killed. This is set while a
+    /// module is being computed, so that each module can have its own timeout.
+    static COMMAND_TIMEOUT: Cell<Option<Duration>> = const { Cell::new(None) };
+}
+
+/// Run `f` with a timeout for the external commands it executes on this thread
+pub fn with_command_timeout<T, F: FnOnce() -> T>(timeout: Duration, f: F) -> T {
+    let previous = COMMAND_TIMEOUT.with(|cell| cell.replace(Some(timeout)));
+    let result = f();
+    COMMAND_TIMEOUT.with(|cell| cell.set(previous));
+    result
+}
+
+/// Execute a command and collect its output, like `Command::output`. If the command runs
+/// longer than the current command timeout, it is killed and an error is returned.
+pub fn command_output(command: &mut Command) -> Result<Output> {
+    let timeout = match COMMAND_TIMEOUT.with(Cell::get) {
+        Some(timeout) => timeout,
+        None => return command.output(),
+    };
+
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    // Read the output on separate threads, so that a child filling up a pipe can't block.
+    // Each thread sends its output once the child closes the pipe, usually by exiting.
+    let (sender, receiver) = mpsc::channel();
+    read_to_end_in_thread(child.stdout.take(), 0, sender.clone());
+    read_to_end_in_thread(child.stderr.take(), 1, sender);
+
+    let deadline = Instant::now() + timeout;
+    let mut outputs = [Vec::new(), Vec::new()];
+    for _ in 0..outputs.len() {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        match receiver.recv_timeout(remaining) {
+            Ok((index, output)) => outputs[index] = output,
+            Err(RecvTimeoutError::Timeout) => {
+                let _ = child.kill();
+                let _ = child.wait();
+                log::debug!(
+                    "Command {:?} was killed after timing out ({:?})",
+                    command,
+                    timeout
+                );
+                return Err(io::Error::new(io::ErrorKind::TimedOut, "command timed out"));
+            }
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+    }
+
+    let [stdout, stderr] = outputs;
+    Ok(Output {
+        status: child.wait()?,
+        stdout,
+        stderr,
+    })
+}
+
+/// Read a pipe of a child to the end, then send its contents along with `index`
+fn read_to_end_in_thread<R>(pipe: Option<R>, index: usize, sender: Sender<(usize, Vec<u8>)>)
+where
+    R: Read + Send + 'static,
+{
+    thread::spawn(move || {
+        let mut buffer = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut buffer);
+        }
+        let _ = sender.send((index, buffer));
+    });
+}
+
+/// Execute a command like `exec_cmd`, reusing its output from the on-disk cache as long as
+/// the binary and the environment variables named in `env` haven't changed. This is meant
+/// for commands whose output only depends on the binary, such as `python --version`.
+pub fn exec_cmd_cached(cmd: &str, args: &[&str], env: &[&str]) -> Option<CommandOutput> {
+    cache::get_or_insert(cmd, args, env, || exec_cmd(cmd, args))
+}
+
 /// Execute a command and return the output on stdout and stderr if sucessful
 pub fn exec_cmd(cmd: &str, args: &[&str]) -> Option<CommandOutput> {
     log::trace!("Executing command '{:?}' with args '{:?}'", cmd, args);
-    match Command::new(cmd).args(args).output() {
+    match command_output(Command::new(cmd).args(args)) {
         Ok(output) => {
             let stdout_string = String::from_utf8(output.stdout).unwrap();
             let stderr_string = String::from_utf8(output.stderr).unwrap();
@@ -53,6 +198,29 @@ pub fn exec_cmd(cmd: &str, args: &[&str]) -> Option<CommandOutput> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn wildcard_patterns() {
+        assert!(matches_wildcard("Cargo.toml", "Cargo.toml"));
+        assert!(!matches_wildcard("Cargo.toml", "Cargo.lock"));
+        assert!(matches_wildcard("*.tf", "main.tf"));
+        assert!(!matches_wildcard("*.tf", "main.tfvars"));
+        assert!(matches_wildcard("prod-*", "prod-eu"));
+        assert!(matches_wildcard("a*b*c", "aXbYbZc"));
+        assert!(matches_wildcard("v?.*", "v1.2"));
+        assert!(!matches_wildcard("v?.*", "v10.2"));
+        assert!(matches_wildcard("*", ""));
+        assert!(!matches_wildcard("?", ""));
+    }
+
+    #[test]
+    fn home_is_expanded() {
+        let home = dirs::home_dir().unwrap();
+        assert_eq!(expand_home("~"), home);
+        assert_eq!(expand_home("~/work"), home.join("work"));
+        assert_eq!(expand_home("~work"), PathBuf::from("~work"));
+        assert_eq!(expand_home("/tmp"), PathBuf::from("/tmp"));
+    }
+
     #[test]
     fn exec_no_output() {
         let result = exec_cmd("true", &[]);
@@ -104,4 +272,27 @@ mod tests {
 
         assert_eq!(result, expected)
     }
+
+    #[test]
+    fn exec_with_timeout() {
+        let result = with_command_timeout(Duration::from_millis(500), || {
+            exec_cmd("/bin/echo", &["-n", "hello"])
+        });
+        let expected = Some(CommandOutput {
+            stdout: String::from("hello"),
+            stderr: String::from(""),
+        });
+
+        assert_eq!(result, expected)
+    }
+
+    #[test]
+    fn exec_timed_out() {
+        let start = Instant::now();
+        let result =
+            with_command_timeout(Duration::from_millis(100), || exec_cmd("sleep", &["10"]));
+
+        assert_eq!(result, None);
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
 }
@@ -17,11 +17,19 @@ fn impl_module_config(dinput: DeriveInput) -> proc_macro::TokenStream {
 
     let mut from_config = quote! {};
     let mut load_config = quote! {};
+    let mut validate = quote! {};
+    let mut known_fields = quote! {};
+    let mut schema = quote! {};
+    let mut to_json = quote! {};
 
     if let syn::Data::Struct(data) = dinput.data {
         if let syn::Fields::Named(fields_named) = data.fields {
             let mut load_tokens = quote! {};
             let mut from_tokens = quote! {};
+            let mut validate_tokens = quote! {};
+            let mut field_names = quote! {};
+            let mut schema_tokens = quote! {};
+            let mut to_json_tokens = quote! {};
 
             for field in fields_named.named.iter() {
                 let ident = field.ident.as_ref().unwrap();
@@ -35,6 +43,9 @@ fn impl_module_config(dinput: DeriveInput) -> proc_macro::TokenStream {
                 let new_from_tokens = quote! {
                     #ident: config.get(stringify!(#ident)).and_then(<#ty>::from_config)?,
                 };
+                let new_validate_tokens = quote! {
+                    stringify!(#ident) => <#ty>::validate(value, palette),
+                };
 
                 load_tokens = quote! {
                     #load_tokens
@@ -43,7 +54,23 @@ fn impl_module_config(dinput: DeriveInput) -> proc_macro::TokenStream {
                 from_tokens = quote! {
                     #from_tokens
                     #new_from_tokens
-                }
+                };
+                validate_tokens = quote! {
+                    #validate_tokens
+                    #new_validate_tokens
+                };
+                field_names = quote! {
+                    #field_names
+                    stringify!(#ident),
+                };
+                schema_tokens = quote! {
+                    #schema_tokens
+                    properties.insert(stringify!(#ident).to_owned(), <#ty>::schema());
+                };
+                to_json_tokens = quote! {
+                    #to_json_tokens
+                    object.insert(stringify!(#ident).to_owned(), self.#ident.to_json());
+                };
             }
 
             load_config = quote! {
@@ -64,13 +91,73 @@ fn impl_module_config(dinput: DeriveInput) -> proc_macro::TokenStream {
                     })
                 }
             };
+            schema = quote! {
+                fn schema() -> serde_json::Value {
+                    let mut properties = serde_json::Map::new();
+                    #schema_tokens
+                    serde_json::json!({
+                        "type": "object",
+                        "properties": properties,
+                        "additionalProperties": false,
+                    })
+                }
+            };
+            to_json = quote! {
+                fn to_json(&self) -> serde_json::Value {
+                    let mut object = serde_json::Map::new();
+                    #to_json_tokens
+                    serde_json::Value::Object(object)
+                }
+            };
+            known_fields = quote! {
+                const KNOWN_FIELDS: &'static [&'static str] = &[#field_names];
+            };
+            validate = quote! {
+                fn validate(
+                    config: &'a toml::Value,
+                    palette: &crate::config::Palette,
+                ) -> Vec<crate::config::ConfigDiagnostic> {
+                    let table = match config.as_table() {
+                        Some(table) => table,
+                        None => {
+                            return vec![crate::config::ConfigDiagnostic::type_mismatch(
+                                "table", config,
+                            )]
+                        }
+                    };
+
+                    let mut diagnostics = Vec::new();
+                    for (key, value) in table.iter() {
+                        let key_diagnostics = match key.as_str() {
+                            #validate_tokens
+                            _ => {
+                                diagnostics.push(crate::config::ConfigDiagnostic::unknown_key(
+                                    key,
+                                    Self::KNOWN_FIELDS,
+                                ));
+                                continue;
+                            }
+                        };
+                        diagnostics.extend(
+                            key_diagnostics
+                                .into_iter()
+                                .map(|diagnostic| diagnostic.in_key(key)),
+                        );
+                    }
+                    diagnostics
+                }
+            };
         }
     }
 
     TokenStream::from(quote! {
         impl<'a> ModuleConfig<'a> for #struct_ident #ty_generics #where_clause {
+            #known_fields
             #from_config
             #load_config
+            #validate
+            #schema
+            #to_json
         }
     })
 }
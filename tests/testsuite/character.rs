@@ -103,7 +103,7 @@ fn char_module_zsh_keymap() -> io::Result<()> {
     // zle keymap is other
     let output = common::render_module("character")
         .env("STARSHIP_SHELL", "zsh")
-        .arg("--keymap=visual")
+        .arg("--keymap=main")
         .output()?;
     let actual = String::from_utf8(output.stdout).unwrap();
     assert!(actual.contains(&expected_other));
@@ -141,10 +141,89 @@ fn char_module_fish_keymap() -> io::Result<()> {
     // fish keymap is other
     let output = common::render_module("character")
         .env("STARSHIP_SHELL", "fish")
-        .arg("--keymap=visual")
+        .arg("--keymap=insert")
         .output()?;
     let actual = String::from_utf8(output.stdout).unwrap();
     assert!(actual.contains(&expected_other));
 
     Ok(())
 }
+
+#[test]
+fn char_module_vi_modes() -> io::Result<()> {
+    let modes = [
+        ("fish", "replace", Color::Purple.bold()),
+        ("fish", "replace_one", Color::Purple.bold()),
+        ("fish", "visual", Color::Yellow.bold()),
+        ("zsh", "vireplace", Color::Purple.bold()),
+        ("zsh", "visual", Color::Yellow.bold()),
+        ("zsh", "viopp", Color::Cyan.bold()),
+    ];
+
+    // The style of the mode is used whatever the status
+    for (shell, keymap, style) in modes.iter() {
+        for status in ["0", "1"].iter() {
+            let output = common::render_module("character")
+                .env("STARSHIP_SHELL", shell)
+                .arg(format!("--keymap={}", keymap))
+                .arg(format!("--status={}", status))
+                .output()?;
+            // zsh prompts wrap the escape sequences in `%{ %}`
+            let actual = String::from_utf8(output.stdout)
+                .unwrap()
+                .replace("%{", "")
+                .replace("%}", "");
+            assert_eq!(format!("{} ", style.paint("❮")), actual);
+        }
+    }
+
+    // fish has no operator-pending mode
+    let output = common::render_module("character")
+        .env("STARSHIP_SHELL", "fish")
+        .arg("--keymap=viopp")
+        .output()?;
+    let actual = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(format!("{} ", Color::Green.bold().paint("❯")), actual);
+
+    Ok(())
+}
+
+#[test]
+fn char_module_vi_mode_options() -> io::Result<()> {
+    let output = common::render_module("character")
+        .use_config(toml::toml! {
+            [character]
+            replace_symbol = "R"
+            replace_one_symbol = "r"
+            visual_symbol = "V"
+            operator_pending_symbol = "O"
+            style_visual = "blue"
+        })
+        .env("STARSHIP_SHELL", "fish")
+        .arg("--keymap=visual")
+        .output()?;
+    let actual = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(format!("{} ", Color::Blue.paint("V")), actual);
+
+    let modes = [
+        ("fish", "replace", "R"),
+        ("fish", "replace_one", "r"),
+        ("zsh", "viopp", "O"),
+    ];
+    for (shell, keymap, symbol) in modes.iter() {
+        let output = common::render_module("character")
+            .use_config(toml::toml! {
+                [character]
+                replace_symbol = "R"
+                replace_one_symbol = "r"
+                operator_pending_symbol = "O"
+            })
+            .env("STARSHIP_SHELL", shell)
+            .arg(format!("--keymap={}", keymap))
+            .output()?;
+        let actual = String::from_utf8(output.stdout).unwrap();
+        assert!(actual.contains(symbol));
+    }
+
+    Ok(())
+}
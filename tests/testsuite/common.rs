@@ -42,6 +42,19 @@ pub fn render_module(module_name: &str) -> process::Command {
     command
 }
 
+/// Render the full init script of a shell
+pub fn render_init(shell: &str) -> process::Command {
+    let mut command = process::Command::new(EXE_PATH);
+
+    command
+        .args(["init", shell, "--print-full-init"])
+        .env_clear()
+        .env("PATH", env!("PATH"))
+        .env("STARSHIP_CONFIG", EMPTY_CONFIG.as_os_str());
+
+    command
+}
+
 /// Create a repo from the fixture to be used in git module tests
 pub fn create_fixture_repo() -> io::Result<PathBuf> {
     let fixture_repo_path = tempfile::tempdir()?.path().join("fixture");
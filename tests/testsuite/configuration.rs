@@ -1,5 +1,6 @@
-use ansi_term::Color;
-use std::io;
+use ansi_term::{Color, Style};
+use std::process::Command;
+use std::{fs, io};
 
 use crate::common::{self, TestCommand};
 
@@ -39,3 +40,678 @@ fn add_newline_configuration() -> io::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn format_with_literal_text() -> io::Result<()> {
+    let output = common::render_prompt()
+        .use_config(toml::toml! {
+            add_newline = false
+            format = "<$directory>$character"
+        })
+        .arg("--path=/")
+        .output()?;
+    let actual = String::from_utf8(output.stdout).unwrap();
+
+    // The directory's prefix and suffix are replaced by the surrounding text
+    let expected = format!(
+        "\u{1b}[J<{}>{} ",
+        Color::Cyan.bold().paint("/"),
+        Color::Green.bold().paint("❯")
+    );
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[test]
+fn legacy_prompt_order() -> io::Result<()> {
+    let output = common::render_prompt()
+        .use_config(toml::toml! {
+            add_newline = false
+            prompt_order = ["directory", "character"]
+        })
+        .arg("--path=/")
+        .output()?;
+    let actual = String::from_utf8(output.stdout).unwrap();
+
+    let expected = format!(
+        "\u{1b}[J{} {} ",
+        Color::Cyan.bold().paint("/"),
+        Color::Green.bold().paint("❯")
+    );
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[test]
+fn format_keeps_affixes_between_modules() -> io::Result<()> {
+    let output = common::render_prompt()
+        .use_config(toml::toml! {
+            add_newline = false
+            format = "$character$directory"
+        })
+        .arg("--path=/")
+        .output()?;
+    let actual = String::from_utf8(output.stdout).unwrap();
+
+    let expected = format!(
+        "\u{1b}[J{} in {} ",
+        Color::Green.bold().paint("❯"),
+        Color::Cyan.bold().paint("/")
+    );
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[test]
+fn format_all_skips_named_modules() -> io::Result<()> {
+    let output = common::render_prompt()
+        .use_config(toml::toml! {
+            add_newline = false
+            format = "$all$directory"
+        })
+        .arg("--path=/")
+        .output()?;
+    let actual = String::from_utf8(output.stdout).unwrap();
+
+    // The directory is only rendered once, after the character
+    let expected_end = format!(
+        "{} in {} ",
+        Color::Green.bold().paint("❯"),
+        Color::Cyan.bold().paint("/")
+    );
+    assert!(actual.ends_with(&expected_end));
+    assert_eq!(actual.matches('/').count(), 1);
+    Ok(())
+}
+
+#[test]
+fn module_format_configuration() -> io::Result<()> {
+    let output = common::render_module("directory")
+        .use_config(toml::toml! {
+            [directory]
+            format = "at [$path](underline red) "
+        })
+        .arg("--path=/")
+        .output()?;
+    let actual = String::from_utf8(output.stdout).unwrap();
+
+    let expected = format!("at {} ", Color::Red.underline().paint("/"));
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[test]
+fn module_format_style_variable_and_conditional() -> io::Result<()> {
+    let output = common::render_module("directory")
+        .use_config(toml::toml! {
+            [directory]
+            format = "(\\($unknown\\) )[$path]($style)"
+        })
+        .arg("--path=/")
+        .output()?;
+    let actual = String::from_utf8(output.stdout).unwrap();
+
+    // The conditional group is hidden, as none of its variables have a value
+    let expected = format!("{}", Color::Cyan.bold().paint("/"));
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[test]
+fn format_with_text_group() -> io::Result<()> {
+    let output = common::render_prompt()
+        .use_config(toml::toml! {
+            add_newline = false
+            format = "[>](red)$directory( [$jobs](blue))"
+        })
+        .arg("--path=/")
+        .output()?;
+    let actual = String::from_utf8(output.stdout).unwrap();
+
+    // The jobs module renders nothing, so its conditional group is hidden and the
+    // directory keeps its suffix
+    let expected = format!(
+        "\u{1b}[J{}{} ",
+        Color::Red.paint(">"),
+        Color::Cyan.bold().paint("/")
+    );
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[test]
+fn right_format_configuration() -> io::Result<()> {
+    let output = common::render_prompt()
+        .use_config(toml::toml! {
+            right_format = "<$directory>"
+        })
+        .arg("--right")
+        .arg("--path=/")
+        .output()?;
+    let actual = String::from_utf8(output.stdout).unwrap();
+
+    // The right prompt has neither a leading newline nor a clear-screen sequence
+    let expected = format!("<{}>", Color::Cyan.bold().paint("/"));
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[test]
+fn format_all_skips_right_format_modules() -> io::Result<()> {
+    let output = common::render_prompt()
+        .use_config(toml::toml! {
+            format = "$all"
+            right_format = "$directory"
+        })
+        .arg("--path=/")
+        .output()?;
+    let actual = String::from_utf8(output.stdout).unwrap();
+
+    assert_eq!(actual.matches('/').count(), 0);
+    Ok(())
+}
+
+#[test]
+fn right_format_powershell_is_right_aligned() -> io::Result<()> {
+    let output = common::render_prompt()
+        .use_config(toml::toml! {
+            right_format = "<$directory>"
+        })
+        .env("STARSHIP_SHELL", "powershell")
+        .arg("--right")
+        .arg("--terminal-width=20")
+        .arg("--path=/")
+        .output()?;
+    let actual = String::from_utf8(output.stdout).unwrap();
+
+    let expected = format!(
+        "\u{1b}[s\u{1b}[18G<{}>\u{1b}[u",
+        Color::Cyan.bold().paint("/")
+    );
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[test]
+fn transient_format_configuration() -> io::Result<()> {
+    let output = common::render_prompt()
+        .use_config(toml::toml! {
+            transient_format = "<$directory>"
+        })
+        .arg("--transient")
+        .arg("--path=/")
+        .output()?;
+    let actual = String::from_utf8(output.stdout).unwrap();
+
+    // The transient prompt has neither a leading newline nor a clear-screen sequence
+    let expected = format!("<{}>", Color::Cyan.bold().paint("/"));
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[test]
+fn continuation_prompt_default() -> io::Result<()> {
+    let output = common::render_prompt().arg("--continuation").output()?;
+    let actual = String::from_utf8(output.stdout).unwrap();
+
+    let expected = format!("{} ", Color::Fixed(8).paint("∙"));
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[test]
+fn continuation_prompt_configuration() -> io::Result<()> {
+    let output = common::render_prompt()
+        .use_config(toml::toml! {
+            continuation_prompt = "[>>](bold yellow) "
+        })
+        .arg("--continuation")
+        .output()?;
+    let actual = String::from_utf8(output.stdout).unwrap();
+
+    let expected = format!("{} ", Color::Yellow.bold().paint(">>"));
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[test]
+fn json_prompt_output() -> io::Result<()> {
+    let output = common::render_prompt()
+        .use_config(toml::toml! {
+            format = "$directory$character"
+        })
+        .arg("--json")
+        .arg("--path=/")
+        .output()?;
+    let actual: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+
+    let modules = actual["modules"].as_array().unwrap();
+    assert_eq!(modules.len(), 2);
+    assert_eq!(modules[0]["name"], "directory");
+    assert_eq!(modules[0]["prefix"]["value"], "in ");
+    assert_eq!(modules[0]["segments"][0]["name"], "path");
+    assert_eq!(modules[0]["segments"][0]["value"], "/");
+    assert_eq!(modules[0]["segments"][0]["style"], "bold fg:cyan");
+    assert!(modules[0]["duration_ms"].is_f64());
+    assert_eq!(modules[1]["name"], "character");
+    Ok(())
+}
+
+#[test]
+fn included_and_drop_in_configuration() -> io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    fs::write(
+        dir.path().join("team.toml"),
+        "[character]\nsymbol = \"T\"\nstyle_success = \"red\"\n",
+    )?;
+    fs::write(
+        dir.path().join("starship.toml"),
+        "include = [\"team.toml\"]\n[character]\nsymbol = \"M\"\n",
+    )?;
+
+    let output = common::render_module("character")
+        .env("STARSHIP_CONFIG", dir.path().join("starship.toml"))
+        .output()?;
+    let actual = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(format!("{} ", Color::Red.paint("M")), actual);
+
+    // The files of starship.d are merged over the main file
+    fs::create_dir(dir.path().join("starship.d"))?;
+    fs::write(
+        dir.path().join("starship.d/local.toml"),
+        "[character]\nsymbol = \"L\"\n",
+    )?;
+
+    let output = common::render_module("character")
+        .env("STARSHIP_CONFIG", dir.path().join("starship.toml"))
+        .output()?;
+    let actual = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(format!("{} ", Color::Red.paint("L")), actual);
+
+    dir.close()
+}
+
+#[test]
+fn directory_local_configuration() -> io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    fs::write(
+        dir.path().join(".starship.toml"),
+        "[character]\nsymbol = \"L\"\n",
+    )?;
+
+    // The file is ignored until its directory is trusted
+    let output = common::render_module("character")
+        .arg("--path")
+        .arg(dir.path())
+        .output()?;
+    let actual = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(format!("{} ", Color::Green.bold().paint("❯")), actual);
+
+    let trusted = dir.path().to_str().unwrap();
+    let output = common::render_module("character")
+        .arg("--path")
+        .arg(dir.path())
+        .use_config(toml::toml! {
+            trusted_directories = [trusted]
+        })
+        .output()?;
+    let actual = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(format!("{} ", Color::Green.bold().paint("L")), actual);
+
+    dir.close()
+}
+
+#[test]
+fn display_conditions() -> io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let nested_dir = dir.path().join("a/b");
+    fs::create_dir_all(&nested_dir)?;
+    fs::write(dir.path().join("project.conditions-marker"), "")?;
+    let other_dir = tempfile::tempdir()?;
+    let shown = format!("\u{1b}[J{} ", Color::Green.bold().paint("❯"));
+    let hidden = "\u{1b}[J";
+
+    let render = |path: &std::path::Path, env: Option<&str>| -> io::Result<String> {
+        let mut command = common::render_prompt();
+        command
+            .use_config(toml::toml! {
+                add_newline = false
+                format = "$character"
+
+                [character.when]
+                files = ["*.conditions-marker"]
+                env = { STARSHIP_TEST_STAGE = "prod-*" }
+            })
+            .arg("--path")
+            .arg(path);
+        if let Some(env) = env {
+            command.env("STARSHIP_TEST_STAGE", env);
+        }
+        Ok(String::from_utf8(command.output()?.stdout).unwrap())
+    };
+
+    // The file is found in a parent of the current directory
+    assert_eq!(shown, render(&nested_dir, Some("prod-eu"))?);
+    assert_eq!(hidden, render(&nested_dir, Some("dev"))?);
+    assert_eq!(hidden, render(&nested_dir, None)?);
+    assert_eq!(hidden, render(other_dir.path(), Some("prod-eu"))?);
+
+    let excluded = dir.path().join("a").to_str().unwrap().to_owned();
+    let output = common::render_prompt()
+        .use_config(toml::toml! {
+            add_newline = false
+            format = "$character"
+
+            [character]
+            not_in = [excluded]
+        })
+        .arg("--path")
+        .arg(&nested_dir)
+        .output()?;
+    assert_eq!(hidden, String::from_utf8(output.stdout).unwrap());
+
+    other_dir.close()?;
+    dir.close()
+}
+
+#[test]
+fn display_conditions_over_ssh() -> io::Result<()> {
+    let render = |env: Option<&str>| -> io::Result<String> {
+        let mut command = common::render_prompt();
+        command.use_config(toml::toml! {
+            add_newline = false
+            format = "$character"
+
+            [character]
+            only_if = { ssh = true }
+        });
+        if let Some(env) = env {
+            command.env(env, "1");
+        }
+        Ok(String::from_utf8(command.output()?.stdout).unwrap())
+    };
+
+    let shown = format!("\u{1b}[J{} ", Color::Green.bold().paint("❯"));
+    assert_eq!(shown, render(Some("SSH_CONNECTION"))?);
+    assert_eq!(shown, render(Some("SSH_CLIENT"))?);
+    assert_eq!(shown, render(Some("SSH_TTY"))?);
+    assert_eq!("\u{1b}[J", render(None)?);
+    Ok(())
+}
+
+#[test]
+fn display_condition_files_stop_at_repo_root_and_home() -> io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    fs::write(dir.path().join("project.conditions-marker"), "")?;
+    let repo_dir = dir.path().join("repo");
+    let home_dir = dir.path().join("home");
+    fs::create_dir_all(repo_dir.join("src"))?;
+    fs::create_dir_all(home_dir.join("a"))?;
+    Command::new("git")
+        .args(["init", "--quiet"])
+        .current_dir(&repo_dir)
+        .output()?;
+
+    let render = |path: &std::path::Path| -> io::Result<String> {
+        let output = common::render_prompt()
+            .use_config(toml::toml! {
+                add_newline = false
+                format = "$character"
+
+                [character.when]
+                files = ["*.conditions-marker"]
+            })
+            .arg("--path")
+            .arg(path)
+            .env("HOME", &home_dir)
+            .output()?;
+        Ok(String::from_utf8(output.stdout).unwrap())
+    };
+
+    // The marker is above the repository and the home directory, so it isn't found
+    assert_eq!("\u{1b}[J", render(&repo_dir.join("src"))?);
+    assert_eq!("\u{1b}[J", render(&home_dir.join("a"))?);
+    assert_eq!(
+        format!("\u{1b}[J{} ", Color::Green.bold().paint("❯")),
+        render(dir.path())?
+    );
+    dir.close()
+}
+
+#[test]
+fn palette_colors_in_styles() -> io::Result<()> {
+    let output = common::render_module("character")
+        .use_config(toml::toml! {
+            palette = "custom"
+
+            [palettes.custom]
+            success = "#50fa7b"
+
+            [character]
+            style_success = "bold success"
+        })
+        .output()?;
+    let actual = String::from_utf8(output.stdout).unwrap();
+
+    let expected = format!("{} ", Color::RGB(0x50, 0xfa, 0x7b).bold().paint("❯"));
+    assert_eq!(expected, actual);
+
+    // A malformed palette color leaves the style at its default
+    let output = common::render_module("character")
+        .use_config(toml::toml! {
+            palette = "custom"
+
+            [palettes.custom]
+            success = "#fff"
+
+            [character]
+            style_success = "bold success"
+        })
+        .output()?;
+    assert!(output.status.success());
+    let actual = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(format!("{} ", Color::Green.bold().paint("❯")), actual);
+    Ok(())
+}
+
+#[test]
+fn colors_are_downsampled_for_the_terminal() -> io::Result<()> {
+    let config = toml::toml! {
+        [character]
+        style_success = "bold #ff0000"
+    };
+
+    let output = common::render_module("character")
+        .use_config(config.clone())
+        .env("TERM", "xterm-256color")
+        .output()?;
+    let actual = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(format!("{} ", Color::Fixed(196).bold().paint("❯")), actual);
+
+    let output = common::render_module("character")
+        .use_config(config.clone())
+        .env("TERM", "linux")
+        .output()?;
+    let actual = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(format!("{} ", Color::Red.bold().paint("❯")), actual);
+
+    let output = common::render_module("character")
+        .use_config(config)
+        .env("TERM", "xterm-256color")
+        .env("NO_COLOR", "1")
+        .output()?;
+    let actual = String::from_utf8(output.stdout).unwrap();
+    assert_eq!("❯ ", actual);
+    Ok(())
+}
+
+#[test]
+fn color_mode_overrides_detection() -> io::Result<()> {
+    let output = common::render_module("character")
+        .use_config(toml::toml! {
+            color_mode = "none"
+
+            [character]
+            style_success = "bold #ff0000"
+        })
+        .env("COLORTERM", "truecolor")
+        .output()?;
+    let actual = String::from_utf8(output.stdout).unwrap();
+
+    assert_eq!("❯ ", actual);
+    Ok(())
+}
+
+#[test]
+fn powerline_separators() -> io::Result<()> {
+    let output = common::render_prompt()
+        .use_config(toml::toml! {
+            add_newline = false
+            format = "$directory$env_var$character$line_break$character"
+
+            [powerline]
+            enabled = true
+            separator = ">"
+
+            [directory]
+            background = "blue"
+
+            [env_var]
+            variable = "STARSHIP_MISSING"
+
+            [character]
+            background = "black"
+        })
+        .arg("--path=/")
+        .output()?;
+    let actual = String::from_utf8(output.stdout).unwrap();
+
+    let between = Color::Blue.on(Color::Black).paint(">").to_string();
+    let end = Color::Black.paint(">").to_string();
+    assert!(actual.starts_with(&format!(
+        "\u{1b}[J{}",
+        Color::Cyan.bold().on(Color::Blue).paint("/")
+    )));
+    // The hidden env_var module doesn't end the block of the directory, and each line
+    // ends its last block without starting the next line with a separator
+    assert!(actual.contains(&format!(
+        "{}{}",
+        Style::new().on(Color::Blue).paint(" "),
+        between
+    )));
+    assert!(actual.contains(&format!("{}\n", end)));
+    assert!(actual.ends_with(&end));
+    assert_eq!(actual.matches('>').count(), 3);
+    Ok(())
+}
+
+#[test]
+fn shell_integration_marks() -> io::Result<()> {
+    let output = common::render_prompt()
+        .use_config(toml::toml! {
+            add_newline = false
+            format = "$character"
+        })
+        .output()?;
+    let actual = String::from_utf8(output.stdout).unwrap();
+    assert!(!actual.contains("\u{1b}]"));
+
+    let output = common::render_prompt()
+        .use_config(toml::toml! {
+            add_newline = false
+            format = "$character"
+            shell_integration = true
+        })
+        .arg("--path=/tmp/my project")
+        .output()?;
+    let actual = String::from_utf8(output.stdout).unwrap();
+    assert!(actual.starts_with("\u{1b}[J\u{1b}]7;file://"));
+    assert!(actual.contains("/tmp/my%20project\u{7}\u{1b}]133;A\u{7}"));
+    assert!(actual.ends_with(&format!(
+        "{} \u{1b}]133;B\u{7}",
+        Color::Green.bold().paint("❯")
+    )));
+
+    // The marks take no space in the prompt of bash
+    let output = common::render_prompt()
+        .use_config(toml::toml! {
+            add_newline = false
+            format = "$character"
+            shell_integration = true
+        })
+        .env("STARSHIP_SHELL", "bash")
+        .output()?;
+    let actual = String::from_utf8(output.stdout).unwrap();
+    assert!(actual.starts_with("\u{1b}[J\\[\u{1b}]7;file://"));
+    assert!(actual.contains("\u{7}\\]\\[\u{1b}]133;A\u{7}\\]"));
+    assert!(actual.ends_with("\\[\u{1b}]133;B\u{7}\\]"));
+
+    // The percent-encoding of the working directory isn't read as prompt escapes by zsh
+    let output = common::render_prompt()
+        .use_config(toml::toml! {
+            add_newline = false
+            format = "$character"
+            shell_integration = true
+        })
+        .arg("--path=/tmp/my project")
+        .env("STARSHIP_SHELL", "zsh")
+        .output()?;
+    let actual = String::from_utf8(output.stdout).unwrap();
+    assert!(actual.starts_with("\u{1b}[J%{\u{1b}]7;file://"));
+    assert!(actual.contains("/tmp/my%%20project\u{7}%}%{\u{1b}]133;A\u{7}%}"));
+    Ok(())
+}
+
+#[test]
+fn title() -> io::Result<()> {
+    let config = toml::toml! {
+        add_newline = false
+        format = "$character"
+
+        [title]
+        enabled = true
+        format = "$directory( — $git_branch)"
+        command_format = "$command in $directory"
+    };
+
+    let output = common::render_prompt()
+        .use_config(config.clone())
+        .arg("--path=/")
+        .output()?;
+    let actual = String::from_utf8(output.stdout).unwrap();
+    assert!(actual.starts_with("\u{1b}[J\u{1b}]0;/\u{7}"));
+
+    let output = common::render_prompt()
+        .use_config(config.clone())
+        .args(["--title", "--path=/", "--command=cargo build\n  --release"])
+        .output()?;
+    let actual = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(actual, "\u{1b}]0;cargo build   --release in /\u{7}");
+
+    // fish sets the title from the output of `fish_title`, so only the text is printed
+    let output = common::render_prompt()
+        .use_config(config.clone())
+        .args(["--title", "--path=/", "--command=ls"])
+        .env("STARSHIP_SHELL", "fish")
+        .output()?;
+    let actual = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(actual, "ls in /");
+    let output = common::render_prompt()
+        .use_config(config.clone())
+        .arg("--path=/")
+        .env("STARSHIP_SHELL", "fish")
+        .output()?;
+    let actual = String::from_utf8(output.stdout).unwrap();
+    assert!(!actual.contains("\u{1b}]0;"));
+
+    // The title is left alone unless it is enabled
+    let output = common::render_prompt()
+        .use_config(toml::toml! {
+            add_newline = false
+            format = "$character"
+        })
+        .args(["--title", "--command=ls"])
+        .output()?;
+    assert!(output.stdout.is_empty());
+    Ok(())
+}
@@ -0,0 +1,129 @@
+use ansi_term::Color;
+use std::{fs, io};
+
+use crate::common::{self, TestCommand};
+
+#[test]
+fn shows_command_output() -> io::Result<()> {
+    let output = common::render_module("custom.greeting")
+        .use_config(toml::toml! {
+            [custom.greeting]
+            command = "echo hello"
+        })
+        .output()?;
+    let actual = String::from_utf8(output.stdout).unwrap();
+
+    let expected = format!("via {} ", Color::Green.bold().paint("hello"));
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[test]
+fn symbol_style_and_affixes() -> io::Result<()> {
+    let output = common::render_module("custom.deploy")
+        .use_config(toml::toml! {
+            [custom.deploy]
+            command = "echo staging"
+            symbol = "🚀 "
+            style = "red"
+            prefix = "to "
+            suffix = "! "
+        })
+        .output()?;
+    let actual = String::from_utf8(output.stdout).unwrap();
+
+    let expected = format!("to {}! ", Color::Red.paint("🚀 staging"));
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[test]
+fn failing_or_empty_command() -> io::Result<()> {
+    for &command in &["false", "printf ''"] {
+        let output = common::render_module("custom.broken")
+            .use_config(toml::toml! {
+                [custom.broken]
+                command = command
+            })
+            .output()?;
+        let actual = String::from_utf8(output.stdout).unwrap();
+        assert_eq!("", actual);
+    }
+    Ok(())
+}
+
+#[test]
+fn when_command() -> io::Result<()> {
+    let render = |when: &str| -> io::Result<String> {
+        let output = common::render_module("custom.vpn")
+            .use_config(toml::toml! {
+                [custom.vpn]
+                command = "echo up"
+                when = when
+            })
+            .output()?;
+        Ok(String::from_utf8(output.stdout).unwrap())
+    };
+
+    assert_eq!(
+        format!("via {} ", Color::Green.bold().paint("up")),
+        render("true")?
+    );
+    assert_eq!("", render("false")?);
+    Ok(())
+}
+
+#[test]
+fn detects_files() -> io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let render = || -> io::Result<String> {
+        let output = common::render_module("custom.deploy")
+            .use_config(toml::toml! {
+                [custom.deploy]
+                command = "echo prod"
+                files = ["deploy.yaml"]
+                extensions = ["tf"]
+            })
+            .arg("--path")
+            .arg(dir.path())
+            .output()?;
+        Ok(String::from_utf8(output.stdout).unwrap())
+    };
+
+    assert_eq!("", render()?);
+    fs::write(dir.path().join("main.tf"), "")?;
+    assert_eq!(
+        format!("via {} ", Color::Green.bold().paint("prod")),
+        render()?
+    );
+    dir.close()
+}
+
+#[test]
+fn used_in_prompt_format() -> io::Result<()> {
+    let output = common::render_prompt()
+        .use_config(toml::toml! {
+            add_newline = false
+            format = "$custom.b$custom"
+
+            [custom.a]
+            command = "echo a"
+
+            [custom.b]
+            command = "echo b"
+
+            [custom.c]
+            command = "echo c"
+            disabled = true
+        })
+        .output()?;
+    let actual = String::from_utf8(output.stdout).unwrap();
+
+    let expected = format!(
+        "\u{1b}[J{} via {} ",
+        Color::Green.bold().paint("b"),
+        Color::Green.bold().paint("a")
+    );
+    assert_eq!(expected, actual);
+    Ok(())
+}
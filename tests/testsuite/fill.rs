@@ -0,0 +1,50 @@
+use ansi_term::Color;
+use std::io;
+
+use crate::common::{self, TestCommand};
+
+#[test]
+fn fills_line_to_terminal_width() -> io::Result<()> {
+    let output = common::render_prompt()
+        .use_config(toml::toml! {
+            add_newline = false
+            format = "$directory$fill$character"
+
+            [fill]
+            symbol = "-"
+            style = "red"
+        })
+        .arg("--path=/")
+        .arg("--terminal-width=12")
+        .output()?;
+    let actual = String::from_utf8(output.stdout).unwrap();
+
+    let expected = format!(
+        "\u{1b}[J{} {}{} ",
+        Color::Cyan.bold().paint("/"),
+        Color::Red.paint("--------"),
+        Color::Green.bold().paint("❯")
+    );
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[test]
+fn fills_share_each_line() -> io::Result<()> {
+    let output = common::render_prompt()
+        .use_config(toml::toml! {
+            add_newline = false
+            format = "[a](red)$fill[b]()$fill[c]()$line_break$fill[d]()"
+
+            [fill]
+            symbol = "·"
+            style = ""
+        })
+        .arg("--terminal-width=8")
+        .output()?;
+    let actual = String::from_utf8(output.stdout).unwrap();
+
+    let expected = format!("\u{1b}[J{}···b··c\n·······d", Color::Red.paint("a"));
+    assert_eq!(expected, actual);
+    Ok(())
+}
@@ -0,0 +1,23 @@
+use std::io;
+
+use crate::common::{self, TestCommand};
+
+#[test]
+fn fish_title() -> io::Result<()> {
+    let output = common::render_init("fish")
+        .use_config(toml::toml! {
+            [title]
+            enabled = true
+        })
+        .output()?;
+    let actual = String::from_utf8(output.stdout).unwrap();
+    assert!(!actual.contains("::TITLE::"));
+    assert!(actual.contains("if true\n    function fish_title\n"));
+    assert!(actual.contains("prompt --title --command=\"$argv[1]\"\n"));
+
+    // The title is left to fish unless it is enabled
+    let output = common::render_init("fish").output()?;
+    let actual = String::from_utf8(output.stdout).unwrap();
+    assert!(actual.contains("if false\n    function fish_title\n"));
+    Ok(())
+}
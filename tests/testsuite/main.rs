@@ -4,9 +4,11 @@ mod cmd_duration;
 mod common;
 mod conda;
 mod configuration;
+mod custom;
 mod directory;
 mod dotnet;
 mod env_var;
+mod fill;
 mod git_branch;
 mod git_commit;
 mod git_state;
@@ -14,6 +16,7 @@ mod git_status;
 mod golang;
 mod hg_branch;
 mod hostname;
+mod init;
 mod jobs;
 mod line_break;
 mod modules;
@@ -21,6 +24,7 @@ mod nix_shell;
 mod nodejs;
 mod python;
 mod ruby;
+mod status;
 mod terraform;
 mod time;
 mod username;
@@ -0,0 +1,89 @@
+use ansi_term::Color;
+use std::io;
+
+use crate::common::{self, TestCommand};
+
+fn render_status(args: &[&str]) -> io::Result<String> {
+    let output = common::render_module("status")
+        .use_config(toml::toml! {
+            [status]
+            disabled = false
+        })
+        .args(args)
+        .output()?;
+    Ok(String::from_utf8(output.stdout).unwrap())
+}
+
+#[test]
+fn disabled_by_default() -> io::Result<()> {
+    let output = common::render_module("status").arg("--status=1").output()?;
+    let actual = String::from_utf8(output.stdout).unwrap();
+
+    assert_eq!("", actual);
+    Ok(())
+}
+
+#[test]
+fn hidden_on_success() -> io::Result<()> {
+    assert_eq!("", render_status(&["--status=0"])?);
+    assert_eq!("", render_status(&["--status=0", "--pipestatus=0 0"])?);
+    Ok(())
+}
+
+#[test]
+fn exit_code() -> io::Result<()> {
+    let expected = format!("{} ", Color::Red.bold().paint("✖ 1"));
+    assert_eq!(expected, render_status(&["--status=1"])?);
+    Ok(())
+}
+
+#[test]
+fn signal_and_shell_meanings() -> io::Result<()> {
+    let expected = format!("{} ", Color::Red.bold().paint("✖ 130 SIGINT"));
+    assert_eq!(expected, render_status(&["--status=130"])?);
+
+    let expected = format!("{} ", Color::Red.bold().paint("✖ 127 not found"));
+    assert_eq!(expected, render_status(&["--status=127"])?);
+
+    let expected = format!("{} ", Color::Red.bold().paint("✖ 126 not executable"));
+    assert_eq!(expected, render_status(&["--status=126"])?);
+    Ok(())
+}
+
+#[test]
+fn pipeline_statuses() -> io::Result<()> {
+    // A failed command in the middle of a pipeline is shown even if the pipeline succeeded
+    let expected = format!("{} ", Color::Red.bold().paint("✖ 0|1|0"));
+    assert_eq!(
+        expected,
+        render_status(&["--status=0", "--pipestatus=0 1 0"])?
+    );
+
+    let expected = format!("{} ", Color::Red.bold().paint("✖ 0|141 SIGPIPE"));
+    assert_eq!(
+        expected,
+        render_status(&["--status=141", "--pipestatus=0 141"])?
+    );
+    Ok(())
+}
+
+#[test]
+fn config_options() -> io::Result<()> {
+    let output = common::render_module("status")
+        .use_config(toml::toml! {
+            [status]
+            disabled = false
+            symbol = "exit "
+            pipestatus_separator = " "
+            show_meaning = false
+            style = "yellow"
+        })
+        .arg("--status=130")
+        .arg("--pipestatus=1 130")
+        .output()?;
+    let actual = String::from_utf8(output.stdout).unwrap();
+
+    let expected = format!("{} ", Color::Yellow.paint("exit 1 130"));
+    assert_eq!(expected, actual);
+    Ok(())
+}
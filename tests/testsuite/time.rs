@@ -41,7 +41,7 @@ fn config_check_prefix_and_suffix() -> io::Result<()> {
         .use_config(toml::toml! {
             [time]
             disabled = false
-            format = "[%T]"
+            time_format = "[%T]"
         })
         .output()?;
     let actual = String::from_utf8(output.stdout).unwrap();
@@ -56,3 +56,21 @@ fn config_check_prefix_and_suffix() -> io::Result<()> {
     assert!(actual.ends_with(&col_suffix));
     Ok(())
 }
+
+#[test]
+fn config_legacy_format() -> io::Result<()> {
+    // Before format strings, `format` held the strftime format of the time
+    let output = common::render_module("time")
+        .use_config(toml::toml! {
+            [time]
+            disabled = false
+            format = "[%Y]"
+        })
+        .output()?;
+    let actual = String::from_utf8(output.stdout).unwrap();
+
+    assert!(actual.starts_with("at "));
+    assert!(!actual.contains('%'));
+    assert!(actual.contains('['));
+    Ok(())
+}